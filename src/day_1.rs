@@ -1,100 +1,336 @@
+use std::{error::Error, fmt::Display, num::ParseIntError};
+
 use aoc_runner_derive::{aoc, aoc_generator};
 
-enum Dir {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dir {
     Left = 0,
     Right = 1,
 }
 
-struct Rotation {
-    dir: Dir,
-    num_clicks: u32,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rotation {
+    pub dir: Dir,
+    pub num_clicks: u64,
+}
+
+impl Display for Rotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let dir = match self.dir {
+            Dir::Left => 'L',
+            Dir::Right => 'R',
+        };
+        write!(f, "{dir}{}", self.num_clicks)
+    }
 }
 
 type Input = Vec<Rotation>;
 
-#[aoc_generator(day1)]
-fn parse(input: &str) -> Input {
-    input
-        .lines()
-        .map(|line| {
-            let dir = match line.chars().next().unwrap() {
-                'R' => Dir::Right,
-                'L' => Dir::Left,
-                _ => unreachable!(),
-            };
-            let count = line.split_at(1).1.parse().unwrap();
-
-            Rotation {
-                dir,
-                num_clicks: count,
-            }
-        })
-        .collect()
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    EmptyLine,
+    UnknownDirection,
+    InvalidClicks(ParseIntError),
 }
 
-#[aoc(day1, part1)]
-fn part1(input: &Input) -> String {
-    const NUM_POS: u32 = 100;
-
-    let mut current_pos: u32 = 50;
+/// Where and why a line of the rotation list could not be parsed.
+/// `line` and `column` are 1-based, `text` is the offending part of the line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+    pub kind: ParseErrorKind,
+}
 
-    let mut times_zero = 0;
-    for rot in input {
-        let num_clicks = rot.num_clicks % NUM_POS;
-        match rot.dir {
-            Dir::Left => {
-                current_pos = current_pos
-                    .checked_sub(num_clicks)
-                    .unwrap_or_else(|| NUM_POS - (num_clicks - current_pos));
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            line,
+            column,
+            text,
+            kind,
+        } = self;
+        match kind {
+            ParseErrorKind::EmptyLine => write!(f, "{line}:{column}: unexpected empty line"),
+            ParseErrorKind::UnknownDirection => {
+                write!(
+                    f,
+                    "{line}:{column}: unknown direction {text:?}, expected 'L' or 'R'"
+                )
             }
-            Dir::Right => {
-                current_pos += num_clicks;
-                current_pos %= NUM_POS;
+            ParseErrorKind::InvalidClicks(err) => {
+                write!(f, "{line}:{column}: invalid click count {text:?}: {err}")
             }
         }
+    }
+}
 
-        if current_pos == 0 {
-            times_zero += 1;
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            ParseErrorKind::InvalidClicks(err) => Some(err),
+            _ => None,
         }
     }
+}
 
-    format!("{times_zero}")
+/// Parses one rotation per line, like `L68`.
+/// Trailing whitespace (including `\r`) on each line and blank lines at the end are ignored.
+///
+/// # Errors
+/// Returns the first line which is not a valid rotation.
+pub fn parse_rotations(input: &str) -> Result<Vec<Rotation>, ParseError> {
+    input
+        .trim_end()
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| parse_rotation(idx + 1, line.trim_end()))
+        .collect()
 }
 
-#[aoc(day1, part2)]
-fn part2(input: &Input) -> String {
-    const NUM_POS: u32 = 100;
+fn parse_rotation(line_num: usize, line: &str) -> Result<Rotation, ParseError> {
+    let error = |column, text: &str, kind| ParseError {
+        line: line_num,
+        column,
+        text: text.to_owned(),
+        kind,
+    };
+
+    let mut chars = line.chars();
+    let dir = match chars.next() {
+        Some('R') => Dir::Right,
+        Some('L') => Dir::Left,
+        Some(c) => return Err(error(1, &c.to_string(), ParseErrorKind::UnknownDirection)),
+        None => return Err(error(1, "", ParseErrorKind::EmptyLine)),
+    };
 
-    let mut current_pos: u32 = 50;
+    let count = chars.as_str();
+    let num_clicks = count
+        .parse()
+        .map_err(|err| error(2, count, ParseErrorKind::InvalidClicks(err)))?;
 
-    let mut times_zero = 0;
-    for rot in input {
-        times_zero += rot.num_clicks / NUM_POS;
-        let num_clicks = rot.num_clicks % NUM_POS;
-        match rot.dir {
+    Ok(Rotation { dir, num_clicks })
+}
+
+#[aoc_generator(day1)]
+fn parse(input: &str) -> Result<Input, ParseError> {
+    parse_rotations(input)
+}
+
+const NUM_POS: u64 = 100;
+const START_POS: u64 = 50;
+
+/// A circular dial with positions `0..size`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dial {
+    size: u64,
+    pos: u64,
+}
+
+impl Dial {
+    /// # Panics
+    /// If `size` is zero or `start` is not a position on the dial.
+    #[must_use]
+    pub fn new(size: u64, start: u64) -> Self {
+        assert!(size > 0, "A dial needs at least one position");
+        assert!(start < size, "{start} is not on a dial of size {size}");
+
+        Self { size, pos: start }
+    }
+
+    #[must_use]
+    pub const fn size(&self) -> u64 {
+        self.size
+    }
+
+    #[must_use]
+    pub const fn position(&self) -> u64 {
+        self.pos
+    }
+
+    #[must_use]
+    pub const fn is_at_zero(&self) -> bool {
+        self.pos == 0
+    }
+
+    /// Applies `rot` and returns how many times the dial pointed at 0 during it,
+    /// including the final position but not the starting one.
+    pub const fn rotate(&mut self, rot: &Rotation) -> u64 {
+        let full_turns = rot.num_clicks / self.size;
+        let rest = rot.num_clicks % self.size;
+
+        let (new_pos, crossed) = match rot.dir {
             Dir::Left => {
-                current_pos = current_pos.checked_sub(num_clicks).map_or_else(
-                    || {
-                        if current_pos != 0 {
-                            times_zero += 1;
-                        }
-                        NUM_POS - (num_clicks - current_pos)
-                    },
-                    |v| v,
-                );
-                if current_pos == 0 {
-                    times_zero += 1;
+                if rest < self.pos {
+                    (self.pos - rest, false)
+                } else {
+                    // We reach 0 (unless we already started there)
+                    ((self.pos + (self.size - rest)) % self.size, self.pos != 0)
                 }
             }
             Dir::Right => {
-                current_pos += num_clicks;
-                if current_pos >= NUM_POS {
-                    times_zero += 1;
+                // Written this way around so huge dial sizes cannot overflow
+                if rest < self.size - self.pos {
+                    (self.pos + rest, false)
+                } else {
+                    (rest - (self.size - self.pos), true)
                 }
-                current_pos %= NUM_POS;
             }
+        };
+
+        self.pos = new_pos;
+        full_turns + crossed as u64
+    }
+}
+
+/// What happened to the dial during a single rotation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceStep {
+    pub rotation: Rotation,
+    pub before: u64,
+    pub after: u64,
+    /// What part 2 counts
+    pub zero_crossings: u64,
+    /// What part 1 counts
+    pub landed_on_zero: bool,
+}
+
+/// Runs `rotations` on `dial`, yielding one [`TraceStep`] per rotation.
+pub fn trace<'a>(
+    mut dial: Dial,
+    rotations: impl IntoIterator<Item = &'a Rotation>,
+) -> impl Iterator<Item = TraceStep> {
+    rotations.into_iter().map(move |rotation| {
+        let before = dial.position();
+        let zero_crossings = dial.rotate(rotation);
+
+        TraceStep {
+            rotation: *rotation,
+            before,
+            after: dial.position(),
+            zero_crossings,
+            landed_on_zero: dial.is_at_zero(),
+        }
+    })
+}
+
+/// Writes the steps as CSV with a header line, numbering the steps from 1.
+///
+/// # Errors
+/// Forwards any error from `out`.
+pub fn write_trace_csv(
+    out: &mut impl std::io::Write,
+    steps: impl IntoIterator<Item = TraceStep>,
+) -> std::io::Result<()> {
+    writeln!(
+        out,
+        "step,rotation,before,after,zero_crossings,landed_on_zero"
+    )?;
+    for (idx, step) in steps.into_iter().enumerate() {
+        let TraceStep {
+            rotation,
+            before,
+            after,
+            zero_crossings,
+            landed_on_zero,
+        } = step;
+        writeln!(
+            out,
+            "{},{rotation},{before},{after},{zero_crossings},{landed_on_zero}",
+            idx + 1
+        )?;
+    }
+    Ok(())
+}
+
+/// Returns how often `rotations` leave `dial` at 0 (part 1) and how often it points at 0 in total (part 2).
+pub fn count_zeros<'a>(
+    dial: Dial,
+    rotations: impl IntoIterator<Item = &'a Rotation>,
+) -> (u64, u64) {
+    trace(dial, rotations).fold((0, 0), |(landings, crossings), step| {
+        (
+            landings + u64::from(step.landed_on_zero),
+            crossings + step.zero_crossings,
+        )
+    })
+}
+
+/// Builds a rotation sequence which makes `dial` point at 0 exactly `crossings` times.
+/// Returns `None` if the number of clicks needed does not fit in a [`Rotation`].
+///
+/// # Panics
+/// If the generated sequence does not match the forward solver.
+#[must_use]
+pub fn rotations_for_crossings(dial: Dial, crossings: u64) -> Option<Vec<Rotation>> {
+    let rotations = if crossings == 0 {
+        vec![]
+    } else {
+        // Reaching 0 from the start takes `size - position` clicks, every further crossing a full turn
+        let clicks_to_zero = dial.size() - dial.position();
+        vec![right(
+            clicks_to_zero.checked_add((crossings - 1).checked_mul(dial.size())?)?,
+        )]
+    };
+
+    assert_eq!(count_zeros(dial, &rotations).1, crossings);
+    Some(rotations)
+}
+
+/// Builds a rotation sequence for which part 1 counts `landings` and part 2 counts `crossings`.
+/// Returns `None` if no such sequence exists on this dial.
+///
+/// # Panics
+/// If the generated sequence does not match the forward solver.
+#[must_use]
+pub fn rotations_for_counts(dial: Dial, landings: u64, crossings: u64) -> Option<Vec<Rotation>> {
+    let rotations = if landings == 0 {
+        if crossings == 0 {
+            vec![]
+        } else {
+            // Pass 0 the required number of times and stop one click after it
+            if dial.size() == 1 {
+                return None;
+            }
+            let mut rotations = rotations_for_crossings(dial, crossings)?;
+            rotations[0].num_clicks = rotations[0].num_clicks.checked_add(1)?;
+            rotations
         }
+    } else {
+        // Do all crossings at once, ending on 0. Every further rotation of 0 clicks counts as landing again.
+        let mut rotations = if dial.is_at_zero() {
+            vec![right(crossings.checked_mul(dial.size())?)]
+        } else if crossings == 0 {
+            return None;
+        } else {
+            rotations_for_crossings(dial, crossings)?
+        };
+        rotations.extend((1..landings).map(|_| right(0)));
+        rotations
+    };
+
+    assert_eq!(count_zeros(dial, &rotations), (landings, crossings));
+    Some(rotations)
+}
+
+const fn right(num_clicks: u64) -> Rotation {
+    Rotation {
+        dir: Dir::Right,
+        num_clicks,
     }
+}
+
+#[aoc(day1, part1)]
+fn part1(input: &Input) -> String {
+    let (times_zero, _) = count_zeros(Dial::new(NUM_POS, START_POS), input);
+
+    format!("{times_zero}")
+}
+
+#[aoc(day1, part2)]
+fn part2(input: &Input) -> String {
+    let (_, times_zero) = count_zeros(Dial::new(NUM_POS, START_POS), input);
 
     format!("{times_zero}")
 }
@@ -107,11 +343,117 @@ mod tests {
 
     #[test]
     fn part1_example() {
-        assert_eq!(part1(&parse(EXAMPLE)), "3");
+        assert_eq!(part1(&parse(EXAMPLE).unwrap()), "3");
     }
 
     #[test]
     fn part2_example() {
-        assert_eq!(part2(&parse(EXAMPLE)), "6");
+        assert_eq!(part2(&parse(EXAMPLE).unwrap()), "6");
+    }
+
+    #[test]
+    fn dial_huge_rotations() {
+        let mut dial = Dial::new(7, 3);
+        let crossings = dial.rotate(&Rotation {
+            dir: Dir::Right,
+            num_clicks: 7_000_000_004,
+        });
+        assert_eq!(crossings, 1_000_000_001);
+        assert!(dial.is_at_zero());
+
+        let crossings = dial.rotate(&Rotation {
+            dir: Dir::Left,
+            num_clicks: 14_000_000_000,
+        });
+        assert_eq!(crossings, 2_000_000_000);
+        assert!(dial.is_at_zero());
+    }
+
+    #[test]
+    fn parse_tolerates_line_endings() {
+        let rotations = parse("R5 \r\nL10\r\n\n").unwrap();
+        assert_eq!(
+            rotations,
+            [
+                Rotation {
+                    dir: Dir::Right,
+                    num_clicks: 5
+                },
+                Rotation {
+                    dir: Dir::Left,
+                    num_clicks: 10
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_reports_position() {
+        let err = parse("R5\n\nL1").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+        assert_eq!(err.kind, ParseErrorKind::EmptyLine);
+
+        let err = parse("R5\nX3").unwrap_err();
+        assert_eq!((err.line, err.column, err.text.as_str()), (2, 1, "X"));
+
+        let err = parse("R5\nL1x").unwrap_err();
+        assert_eq!((err.line, err.column, err.text.as_str()), (2, 2, "1x"));
+        assert!(matches!(err.kind, ParseErrorKind::InvalidClicks(_)));
+    }
+
+    #[test]
+    fn trace_matches_parts() {
+        let input = parse(EXAMPLE).unwrap();
+        let steps = trace(Dial::new(NUM_POS, START_POS), &input).collect::<Vec<_>>();
+
+        assert_eq!(steps.len(), input.len());
+        assert_eq!(steps.iter().filter(|step| step.landed_on_zero).count(), 3);
+        assert_eq!(steps.iter().map(|step| step.zero_crossings).sum::<u64>(), 6);
+        assert!(steps.windows(2).all(|w| w[0].after == w[1].before));
+
+        let mut csv = vec![];
+        write_trace_csv(&mut csv, steps).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("step,rotation,before,after,zero_crossings,landed_on_zero")
+        );
+        assert_eq!(lines.next(), Some("1,L68,50,82,1,false"));
+        assert_eq!(lines.next(), Some("2,L30,82,52,0,false"));
+        assert_eq!(lines.next(), Some("3,R48,52,0,1,true"));
+    }
+
+    #[test]
+    fn synthesized_rotations() {
+        for (size, start) in [(1, 0), (2, 1), (100, 0), (100, 50), (100, 99)] {
+            let dial = Dial::new(size, start);
+            for crossings in 0..5 {
+                assert!(rotations_for_crossings(dial, crossings).unwrap().len() <= 1);
+
+                for landings in 0..5 {
+                    let possible = match (landings, crossings) {
+                        (0, 0) => true,
+                        (0, _) => size > 1,
+                        (_, 0) => start == 0,
+                        _ => true,
+                    };
+                    assert_eq!(
+                        rotations_for_counts(dial, landings, crossings).is_some(),
+                        possible,
+                        "{dial:?} {landings} {crossings}"
+                    );
+                }
+            }
+        }
+
+        assert_eq!(rotations_for_crossings(Dial::new(100, 50), u64::MAX), None);
+
+        // The parts use the same forward solver the synthesized rotations are checked against
+        let rotations = rotations_for_counts(Dial::new(NUM_POS, START_POS), 3, 7).unwrap();
+        assert_eq!(
+            (part1(&rotations), part2(&rotations)),
+            ("3".into(), "7".into())
+        );
     }
 }
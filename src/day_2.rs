@@ -1,9 +1,26 @@
-use std::ops::RangeInclusive;
+use std::{
+    cmp::{max, min},
+    error::Error,
+    fmt::Display,
+    ops::RangeInclusive,
+};
 
 use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::Itertools;
 
-type Input = Vec<RangeInclusive<u64>>;
+type Input = Vec<RangeInclusive<u128>>;
+
+/// The sum of invalid ids does not fit in a [`u128`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overflow;
+
+impl Display for Overflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "sum of invalid ids overflowed")
+    }
+}
+
+impl Error for Overflow {}
 
 #[aoc_generator(day2)]
 fn parse(input: &str) -> Input {
@@ -16,47 +33,394 @@ fn parse(input: &str) -> Input {
         .collect()
 }
 
-#[aoc(day2, part1)]
-fn part1(input: &Input) -> String {
-    let sum: u64 = input
-        .iter()
-        .flat_map(std::clone::Clone::clone)
-        .filter(|val| {
-            let id_str = format!("{val}");
-            let (left_half, right_half) = id_str.split_at(id_str.len() / 2);
-
-            left_half == right_half
-        })
-        .sum();
+/// Which numbers of repetitions of a pattern make an id invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Repeats {
+    Exactly(u32),
+    AtLeast(u32),
+}
 
-    format!("{sum}")
+impl Repeats {
+    #[must_use]
+    pub const fn allows(self, repeats: u32) -> bool {
+        match self {
+            Self::Exactly(allowed) => repeats == allowed,
+            Self::AtLeast(min) => repeats >= min,
+        }
+    }
 }
 
-#[aoc(day2, part2)]
-fn part2(input: &Input) -> String {
-    let sum: u64 = input
-        .iter()
-        .flat_map(std::clone::Clone::clone)
-        .filter(|val| {
-            let id_str = format!("{val}");
-            let id_str = id_str.as_bytes();
-            for num_parts in 2..=id_str.len() {
-                if id_str.len() % num_parts != 0 {
-                    continue;
-                }
-                let substr_len = id_str.len() / num_parts;
-                let (substr, rest) = id_str.split_at(substr_len);
-                if rest.chunks(substr_len).all(|rest| rest == substr) {
-                    // Value is invalid
-                    return true;
-                }
+/// An id is invalid if its digits in `base` (without leading zeros) are a pattern repeated a number of times allowed by `repeats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rule {
+    base: u32,
+    repeats: Repeats,
+}
+
+impl Rule {
+    pub const PART1: Self = Self::new(10, Repeats::Exactly(2));
+    pub const PART2: Self = Self::new(10, Repeats::AtLeast(2));
+
+    /// # Panics
+    /// If `base` is not in `2..=36`.
+    #[must_use]
+    pub const fn new(base: u32, repeats: Repeats) -> Self {
+        assert!(base >= 2 && base <= 36);
+        Self { base, repeats }
+    }
+
+    #[must_use]
+    pub const fn base(&self) -> u32 {
+        self.base
+    }
+
+    #[must_use]
+    pub const fn repeats(&self) -> Repeats {
+        self.repeats
+    }
+
+    #[must_use]
+    pub fn is_invalid(&self, id: u128) -> bool {
+        self.split(id).is_some()
+    }
+
+    /// Splits an invalid id into its pattern, using the most repetitions allowed.
+    #[must_use]
+    pub fn split(&self, id: u128) -> Option<InvalidId> {
+        let digits = self.digits(id);
+        (1..=digits.len())
+            .rev()
+            .filter(|repeats| digits.len().is_multiple_of(*repeats))
+            .filter_map(|repeats| Some((u32::try_from(repeats).ok()?, digits.len() / repeats)))
+            .find(|(repeats, pattern_len)| {
+                self.repeats.allows(*repeats) && digits.chunks(*pattern_len).all_equal()
+            })
+            .map(|(repeats, pattern_len)| InvalidId {
+                id,
+                pattern: digits[..pattern_len]
+                    .iter()
+                    .fold(0, |pattern, digit| pattern * u128::from(self.base) + digit),
+                repeats,
+            })
+    }
+
+    /// Sums all invalid ids in `range`.
+    /// Every id is counted once, even if it can be split in multiple ways (like 111111).
+    ///
+    /// # Errors
+    /// If the sum does not fit in a [`u128`].
+    pub fn sum_invalid(&self, range: &RangeInclusive<u128>) -> Result<u128, Overflow> {
+        self.measure_invalid(range, |multiplier, patterns| {
+            let count = patterns.end() - patterns.start() + 1;
+            let pattern_sum = if count.is_multiple_of(2) {
+                (patterns.start().checked_add(*patterns.end()))
+                    .and_then(|v| v.checked_mul(count / 2))
+            } else {
+                patterns
+                    .start()
+                    .midpoint(*patterns.end())
+                    .checked_mul(count)
+            };
+            pattern_sum.and_then(|sum| sum.checked_mul(multiplier))
+        })
+    }
+
+    /// Counts all invalid ids in `range`.
+    ///
+    /// # Panics
+    /// Never, the count cannot be larger than the range.
+    #[must_use]
+    pub fn count_invalid(&self, range: &RangeInclusive<u128>) -> u128 {
+        // The count is never larger than the number of ids in the range
+        self.measure_invalid(range, |_, patterns| {
+            Some(patterns.end() - patterns.start() + 1)
+        })
+        .expect("Count cannot overflow")
+    }
+
+    /// Lists all invalid ids in `range` in ascending order.
+    /// Make sure to check [`Self::count_invalid`] first, this list can be huge.
+    ///
+    /// # Panics
+    /// Never, all generated ids are invalid.
+    pub fn invalid_ids(&self, range: &RangeInclusive<u128>) -> impl Iterator<Item = InvalidId> {
+        self.id_lens(range).flat_map(move |(len, lo, hi)| {
+            self.pattern_lens(len)
+                .filter_map(|pattern_len| self.patterns(lo, hi, len, pattern_len))
+                .flat_map(|(multiplier, patterns)| {
+                    patterns.map(move |pattern| pattern * multiplier)
+                })
+                // An id might be generated by multiple pattern lengths
+                .sorted_unstable()
+                .dedup()
+                .map(|id| self.split(id).expect("Generated ids are invalid"))
+        })
+    }
+
+    /// Accumulates `measure(multiplier, patterns)` over all invalid ids, counting ids which can be split in multiple ways once.
+    fn measure_invalid(
+        &self,
+        range: &RangeInclusive<u128>,
+        measure: impl Fn(u128, RangeInclusive<u128>) -> Option<u128>,
+    ) -> Result<u128, Overflow> {
+        self.id_lens(range).try_fold(0u128, |total, (len, lo, hi)| {
+            // Every id of this length has a shortest repeating pattern, whose length divides len.
+            // It can be repeated into any longer pattern whose length is a multiple of it,
+            // so if a pattern length is counted, all its divisors are as well.
+            let pattern_lens = self.pattern_lens(len).collect_vec();
+
+            // Measure the ids for every possible shortest pattern length, so every id is only counted once.
+            let mut by_shortest_pattern: Vec<u128> = vec![];
+            for &pattern_len in &pattern_lens {
+                // These are all part of the periodic measure, so they cannot overflow
+                let shorter: u128 = pattern_lens
+                    .iter()
+                    .zip(&by_shortest_pattern)
+                    .filter(|(shorter_len, _)| pattern_len.is_multiple_of(**shorter_len))
+                    .map(|(_, measured)| measured)
+                    .sum();
+                let periodic = match self.patterns(lo, hi, len, pattern_len) {
+                    Some((multiplier, patterns)) => {
+                        measure(multiplier, patterns).ok_or(Overflow)?
+                    }
+                    None => 0,
+                };
+                by_shortest_pattern.push(periodic - shorter);
+            }
+
+            by_shortest_pattern
+                .into_iter()
+                .try_fold(total, u128::checked_add)
+                .ok_or(Overflow)
+        })
+    }
+
+    /// Splits `range` by number of digits, returning `(len, lo, hi)`.
+    fn id_lens(&self, range: &RangeInclusive<u128>) -> impl Iterator<Item = (u32, u128, u128)> {
+        let base = u128::from(self.base);
+        let (start, end) = (*range.start(), *range.end());
+        (self.num_digits(start)..=self.num_digits(end))
+            .map(move |len| {
+                let lo = max(start, base.pow(len - 1));
+                let hi = base
+                    .checked_pow(len)
+                    .map_or(end, |limit| min(end, limit - 1));
+                (len, lo, hi)
+            })
+            .filter(|(_, lo, hi)| lo <= hi)
+    }
+
+    /// All pattern lengths for ids with `len` digits which can be repeated an allowed number of times.
+    fn pattern_lens(&self, len: u32) -> impl Iterator<Item = u32> {
+        (1..=len).filter(move |pattern_len| {
+            len.is_multiple_of(*pattern_len) && {
+                let max_repeats = len / pattern_len;
+                (1..=max_repeats).any(|repeats| {
+                    max_repeats.is_multiple_of(repeats) && self.repeats.allows(repeats)
+                })
+            }
+        })
+    }
+
+    /// Finds the patterns of length `pattern_len` which, when repeated up to `len` digits, land in `lo..=hi`.
+    /// Returns the multiplier repeating a pattern, and the range of patterns.
+    fn patterns(
+        &self,
+        lo: u128,
+        hi: u128,
+        len: u32,
+        pattern_len: u32,
+    ) -> Option<(u128, RangeInclusive<u128>)> {
+        let base = u128::from(self.base);
+        // This only overflows if the pattern is the whole id, in which case every id up to u128::MAX is a pattern
+        let pattern_limit = base.checked_pow(pattern_len);
+        let max_pattern = pattern_limit.map_or(u128::MAX, |limit| limit - 1);
+        // Repeating a pattern is the same as multiplying it with 1..01..01
+        let multiplier = (1..len / pattern_len).try_fold(1u128, |multiplier, _| {
+            multiplier.checked_mul(pattern_limit?)?.checked_add(1)
+        })?;
+
+        let first_pattern = max(lo.div_ceil(multiplier), base.pow(pattern_len - 1));
+        let last_pattern = min(hi / multiplier, max_pattern);
+
+        (first_pattern <= last_pattern).then_some((multiplier, first_pattern..=last_pattern))
+    }
+
+    const fn num_digits(&self, val: u128) -> u32 {
+        match val.checked_ilog(self.base as u128) {
+            Some(log) => log + 1,
+            None => 1,
+        }
+    }
+
+    fn format(&self, val: u128) -> String {
+        self.digits(val)
+            .into_iter()
+            .map(|digit| {
+                char::from_digit(u32::try_from(digit).unwrap(), self.base)
+                    .expect("Digits are less than base")
+            })
+            .collect()
+    }
+
+    /// Most significant digit first
+    fn digits(&self, mut val: u128) -> Vec<u128> {
+        let base = u128::from(self.base);
+        let mut digits = vec![];
+        loop {
+            digits.push(val % base);
+            val /= base;
+            if val == 0 {
+                break;
             }
+        }
+        digits.reverse();
+        digits
+    }
+}
+
+/// An invalid id, consisting of `pattern` repeated `repeats` times.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidId {
+    pub id: u128,
+    pub pattern: u128,
+    pub repeats: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeReport {
+    pub range: RangeInclusive<u128>,
+    pub count: u128,
+    pub sum: u128,
+    /// `None` if there are too many to list
+    pub ids: Option<Vec<InvalidId>>,
+}
 
-            false
+/// The invalid ids of every range. Its [`Display`] implementation prints it as a table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub rule: Rule,
+    pub ranges: Vec<RangeReport>,
+}
+
+impl Rule {
+    /// Lists the invalid ids of every range, as long as there are at most `max_listed` in a range.
+    ///
+    /// # Errors
+    /// If the sum of any range does not fit in a [`u128`].
+    pub fn report(
+        &self,
+        ranges: &[RangeInclusive<u128>],
+        max_listed: usize,
+    ) -> Result<Report, Overflow> {
+        let ranges = ranges
+            .iter()
+            .map(|range| {
+                let count = self.count_invalid(range);
+                let ids = usize::try_from(count)
+                    .is_ok_and(|count| count <= max_listed)
+                    .then(|| self.invalid_ids(range).collect());
+
+                Ok(RangeReport {
+                    range: range.clone(),
+                    count,
+                    sum: self.sum_invalid(range)?,
+                    ids,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Report {
+            rule: *self,
+            ranges,
         })
-        .sum();
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rows = self
+            .ranges
+            .iter()
+            .map(|report| {
+                let ids = report.ids.as_ref().map_or_else(
+                    || "too many to list".to_owned(),
+                    |ids| {
+                        if ids.is_empty() {
+                            return "-".to_owned();
+                        }
+                        ids.iter()
+                            .map(|id| {
+                                format!(
+                                    "{} ({} x{})",
+                                    self.rule.format(id.id),
+                                    self.rule.format(id.pattern),
+                                    id.repeats
+                                )
+                            })
+                            .join(", ")
+                    },
+                );
+                [
+                    format!("{}-{}", report.range.start(), report.range.end()),
+                    report.count.to_string(),
+                    report.sum.to_string(),
+                    ids,
+                ]
+            })
+            .collect_vec();
+
+        let header = [
+            "range".to_owned(),
+            "count".to_owned(),
+            "sum".to_owned(),
+            format!("invalid ids (base {})", self.rule.base),
+        ];
+        let widths = (0..3)
+            .map(|col| {
+                rows.iter()
+                    .chain([&header])
+                    .map(|row| row[col].len())
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect_vec();
+
+        for row in std::iter::once(&header).chain(&rows) {
+            writeln!(
+                f,
+                "{:<w0$}  {:>w1$}  {:>w2$}  {}",
+                row[0],
+                row[1],
+                row[2],
+                row[3],
+                w0 = widths[0],
+                w1 = widths[1],
+                w2 = widths[2],
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn sum_all(input: &Input, rule: Rule) -> Result<u128, Overflow> {
+    input.iter().try_fold(0u128, |sum, range| {
+        sum.checked_add(rule.sum_invalid(range)?).ok_or(Overflow)
+    })
+}
+
+#[aoc(day2, part1)]
+fn part1(input: &Input) -> Result<String, Overflow> {
+    let sum = sum_all(input, Rule::PART1)?;
+
+    Ok(format!("{sum}"))
+}
+
+#[aoc(day2, part2)]
+fn part2(input: &Input) -> Result<String, Overflow> {
+    let sum = sum_all(input, Rule::PART2)?;
 
-    format!("{sum}")
+    Ok(format!("{sum}"))
 }
 
 #[cfg(test)]
@@ -67,11 +431,201 @@ mod tests {
 
     #[test]
     fn part1_example() {
-        assert_eq!(part1(&parse(EXAMPLE)), "1227775554");
+        assert_eq!(part1(&parse(EXAMPLE)).unwrap(), "1227775554");
     }
 
     #[test]
     fn part2_example() {
-        assert_eq!(part2(&parse(EXAMPLE)), "4174379265");
+        assert_eq!(part2(&parse(EXAMPLE)).unwrap(), "4174379265");
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        // Independent of how Rule finds digits and patterns
+        let format = |val: u128, base: u32| match base {
+            2 => format!("{val:b}"),
+            10 => val.to_string(),
+            16 => format!("{val:x}"),
+            _ => std::iter::successors(Some(val), |val| Some(val / u128::from(base)))
+                .take_while(|val| *val > 0)
+                .map(|val| {
+                    let digit = u32::try_from(val % u128::from(base)).unwrap();
+                    char::from_digit(digit, base).unwrap()
+                })
+                .collect::<String>()
+                .chars()
+                .rev()
+                .collect(),
+        };
+        let is_invalid = |digits: &str, repeats: Repeats| {
+            (1..=digits.len()).any(|times| {
+                digits.len().is_multiple_of(times)
+                    && repeats.allows(times.try_into().unwrap())
+                    && digits.as_bytes().chunks(digits.len() / times).all_equal()
+            })
+        };
+
+        let ranges = [
+            0..=0,
+            1..=9,
+            0..=5000,
+            95..=115,
+            999_000..=1_001_200,
+            5..=120_000,
+        ];
+        let rules = [
+            Rule::PART1,
+            Rule::PART2,
+            Rule::new(10, Repeats::Exactly(3)),
+            Rule::new(10, Repeats::AtLeast(3)),
+            Rule::new(2, Repeats::AtLeast(2)),
+            Rule::new(2, Repeats::AtLeast(1)),
+            Rule::new(16, Repeats::Exactly(2)),
+            Rule::new(36, Repeats::AtLeast(2)),
+        ];
+        let cases = ranges
+            .into_iter()
+            .cartesian_product(rules)
+            // A larger range, which is slow to brute force for every rule
+            .chain([(5..=1_200_000, Rule::PART1), (5..=1_200_000, Rule::PART2)]);
+
+        for (range, rule) in cases {
+            let expected: u128 = range
+                .clone()
+                .filter(|&val| is_invalid(&format(val, rule.base()), rule.repeats()))
+                .sum();
+            assert_eq!(rule.sum_invalid(&range), Ok(expected), "{rule:?} {range:?}");
+        }
+    }
+
+    #[test]
+    fn other_bases() {
+        // 0b1010, 0b1111
+        assert!(Rule::new(2, Repeats::Exactly(2)).is_invalid(10));
+        assert!(Rule::new(2, Repeats::Exactly(2)).is_invalid(15));
+        assert!(!Rule::new(2, Repeats::Exactly(2)).is_invalid(7));
+        // 0xABAB
+        assert!(Rule::new(16, Repeats::Exactly(2)).is_invalid(0xABAB));
+        // "zzz" in base 36
+        assert!(Rule::new(36, Repeats::AtLeast(3)).is_invalid(36 * 36 * 35 + 36 * 35 + 35));
+        assert!(!Rule::new(36, Repeats::AtLeast(3)).is_invalid(36 * 35 + 35));
+    }
+
+    #[test]
+    fn huge_range() {
+        // 2 digit patterns repeated 9 times
+        let expected: u128 = (10..=19)
+            .map(|pattern| pattern * 10_101_010_101_010_101)
+            .sum();
+        assert_eq!(
+            Rule::new(10, Repeats::Exactly(9))
+                .sum_invalid(&(100_000_000_000_000_000..=200_000_000_000_000_000)),
+            Ok(expected)
+        );
+    }
+
+    #[test]
+    fn u128_limits() {
+        let max_repeated = u128::from(u64::MAX) * (u128::from(u64::MAX) + 2);
+        assert!(Rule::new(16, Repeats::Exactly(2)).is_invalid(max_repeated));
+        assert_eq!(
+            Rule::new(16, Repeats::Exactly(2)).sum_invalid(&(u128::MAX - 1000..=u128::MAX)),
+            Ok(max_repeated)
+        );
+        assert_eq!(
+            Rule::PART2.sum_invalid(&(u128::MAX - 1000..=u128::MAX)),
+            Ok(0)
+        );
+        // The largest ids have as many digits as the largest patterns
+        for base in [2, 10] {
+            let every_id = Rule::new(base, Repeats::AtLeast(1));
+            assert_eq!(
+                every_id.sum_invalid(&(u128::MAX..=u128::MAX)),
+                Ok(u128::MAX)
+            );
+            assert_eq!(every_id.count_invalid(&(u128::MAX..=u128::MAX)), 1);
+            assert_eq!(every_id.count_invalid(&(u128::MAX - 9..=u128::MAX)), 10);
+            assert_eq!(
+                every_id.sum_invalid(&(u128::MAX - 1..=u128::MAX)),
+                Err(Overflow)
+            );
+        }
+        assert_eq!(
+            Rule::new(16, Repeats::Exactly(2)).sum_invalid(&(0..=u128::MAX)),
+            Err(Overflow)
+        );
+        assert_eq!(
+            sum_all(
+                &vec![max_repeated..=max_repeated; 2],
+                Rule::new(16, Repeats::Exactly(2))
+            ),
+            Err(Overflow)
+        );
+    }
+
+    #[test]
+    fn report_example() {
+        let input = parse(EXAMPLE);
+        let report = Rule::PART2.report(&input, 1).unwrap();
+
+        assert_eq!(report.ranges.len(), input.len());
+        assert_eq!(
+            report.ranges.iter().map(|range| range.sum).sum::<u128>(),
+            4_174_379_265
+        );
+
+        let first = &report.ranges[0];
+        assert_eq!((first.count, first.sum, &first.ids), (2, 33, &None));
+
+        let fifth = &report.ranges[4];
+        assert_eq!(
+            fifth.ids,
+            Some(vec![InvalidId {
+                id: 222_222,
+                pattern: 2,
+                repeats: 6,
+            }])
+        );
+
+        let table = report.to_string();
+        let lines = table.lines().collect_vec();
+        assert_eq!(
+            lines[0],
+            "range                  count         sum  invalid ids (base 10)"
+        );
+        assert_eq!(
+            lines[1],
+            "11-22                      2          33  too many to list"
+        );
+        assert_eq!(
+            lines[4],
+            "1188511880-1188511890      1  1188511885  1188511885 (11885 x2)"
+        );
+        assert_eq!(lines[6], "1698522-1698528            0           0  -");
+    }
+
+    #[test]
+    fn invalid_ids_are_split_by_rule() {
+        let ids = Rule::PART1.invalid_ids(&(1..=111_111)).collect_vec();
+        assert_eq!(ids.len(), 9 + 90 + 12);
+        assert!(ids.iter().tuple_windows().all(|(a, b)| a.id < b.id));
+        assert_eq!(
+            ids.last(),
+            Some(&InvalidId {
+                id: 111_111,
+                pattern: 111,
+                repeats: 2
+            })
+        );
+
+        let ids = Rule::PART2.invalid_ids(&(111_111..=111_111)).collect_vec();
+        assert_eq!(
+            ids,
+            [InvalidId {
+                id: 111_111,
+                pattern: 1,
+                repeats: 6
+            }]
+        );
     }
 }
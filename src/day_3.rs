@@ -1,68 +1,175 @@
+use std::{error::Error, fmt::Display};
+
 use aoc_runner_derive::{aoc, aoc_generator};
+use num_bigint::BigUint;
 
 type Input = Vec<Vec<u8>>;
 
-#[aoc_generator(day3)]
-fn parse(input: &str) -> Input {
+const PART1_BATTERIES: usize = 2;
+const PART2_BATTERIES: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    NotADigit(char),
+    BankTooShort { len: usize, required: usize },
+}
+
+/// `line` and `column` are 1-based. For short banks `column` is just past the end of the line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self { line, column, kind } = self;
+        match kind {
+            ParseErrorKind::NotADigit(c) => write!(f, "{line}:{column}: {c:?} is not a digit"),
+            ParseErrorKind::BankTooShort { len, required } => write!(
+                f,
+                "{line}:{column}: bank has {len} batteries, but at least {required} are required"
+            ),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// Parses one bank of single digit batteries per line, requiring at least `min_len` batteries in every bank.
+///
+/// # Errors
+/// On the first character which is not a digit or the first bank which is too short.
+pub fn parse_banks(input: &str, min_len: usize) -> Result<Vec<Vec<u8>>, ParseError> {
     input
         .lines()
-        .map(|line| line.chars().map(|char| char as u8 - 48).collect())
+        .enumerate()
+        .map(|(line_idx, line)| {
+            let bank = line
+                .chars()
+                .enumerate()
+                .map(|(column_idx, c)| {
+                    c.to_digit(10)
+                        .and_then(|digit| u8::try_from(digit).ok())
+                        .ok_or(ParseError {
+                            line: line_idx + 1,
+                            column: column_idx + 1,
+                            kind: ParseErrorKind::NotADigit(c),
+                        })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            if bank.len() < min_len {
+                return Err(ParseError {
+                    line: line_idx + 1,
+                    column: bank.len() + 1,
+                    kind: ParseErrorKind::BankTooShort {
+                        len: bank.len(),
+                        required: min_len,
+                    },
+                });
+            }
+
+            Ok(bank)
+        })
         .collect()
 }
 
-#[aoc(day3, part1)]
-fn part1(input: &Input) -> String {
-    let sum: u32 = input
-        .iter()
-        .map(|bank| {
-            assert!(bank.len() >= 2);
+#[aoc_generator(day3, part1)]
+fn parse_part1(input: &str) -> Result<Input, ParseError> {
+    parse_banks(input, PART1_BATTERIES)
+}
 
-            let tens = bank.iter().take(bank.len() - 1).max().unwrap();
-            let tens_pos = bank.iter().position(|v| v == tens).unwrap();
-            let ones = bank.iter().skip(tens_pos + 1).max().unwrap();
+#[aoc_generator(day3, part2)]
+fn parse_part2(input: &str) -> Result<Input, ParseError> {
+    parse_banks(input, PART2_BATTERIES)
+}
 
-            u32::from((*tens) * 10 + *ones)
-        })
-        .sum();
+#[aoc(day3, part1)]
+fn part1(input: &Input) -> String {
+    let sum = total_joltage(input, PART1_BATTERIES);
 
     format!("{sum}")
 }
 
 #[aoc(day3, part2)]
 fn part2(input: &Input) -> String {
-    const NUM_BATTERIES: usize = 12;
-    let sum: u64 = input
-        .iter()
-        .map(|bank| {
-            assert!(bank.len() >= NUM_BATTERIES);
-
-            let mut used_range: usize = 0;
-
-            (0..NUM_BATTERIES)
-                .rev()
-                .map(|batt_idx| {
-                    let v = bank
-                        .iter()
-                        .skip(used_range)
-                        .take(bank.len() - batt_idx - used_range)
-                        .max()
-                        .unwrap();
-                    let pos = bank
-                        .iter()
-                        .skip(used_range)
-                        .position(|found| found == v)
-                        .unwrap();
-                    used_range += pos + 1;
-
-                    u64::from(*v) * 10_u64.pow(batt_idx.try_into().unwrap())
-                })
-                .sum::<u64>()
-        })
-        .sum();
+    let sum = total_joltage(input, PART2_BATTERIES);
 
     format!("{sum}")
 }
 
+/// The batteries turned on in a bank, and the resulting joltage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selection {
+    /// Ascending positions in the bank
+    pub indices: Vec<usize>,
+    pub joltage: BigUint,
+}
+
+/// Turns on the `num_batteries` batteries in `bank` which produce the highest joltage.
+///
+/// # Panics
+/// If `bank` has fewer than `num_batteries` batteries.
+#[must_use]
+pub fn select_batteries(bank: &[u8], num_batteries: usize) -> Selection {
+    let indices = largest_subsequence_indices(bank, num_batteries);
+    let joltage = indices
+        .iter()
+        .fold(BigUint::ZERO, |joltage, idx| joltage * 10u8 + bank[*idx]);
+
+    Selection { indices, joltage }
+}
+
+/// Sums the highest joltage of every bank.
+///
+/// # Panics
+/// If any bank has fewer than `num_batteries` batteries.
+#[must_use]
+pub fn total_joltage(banks: &[Vec<u8>], num_batteries: usize) -> BigUint {
+    banks
+        .iter()
+        .map(|bank| select_batteries(bank, num_batteries).joltage)
+        .sum()
+}
+
+/// Returns the lexicographically largest subsequence of `bank` with length `len`.
+///
+/// # Panics
+/// If `bank` is shorter than `len`.
+#[must_use]
+pub fn largest_subsequence(bank: &[u8], len: usize) -> Vec<u8> {
+    largest_subsequence_indices(bank, len)
+        .into_iter()
+        .map(|idx| bank[idx])
+        .collect()
+}
+
+/// Returns the positions of the lexicographically largest subsequence of `bank` with length `len`.
+/// If there are multiple, the earliest positions are chosen.
+///
+/// # Panics
+/// If `bank` is shorter than `len`.
+#[must_use]
+pub fn largest_subsequence_indices(bank: &[u8], len: usize) -> Vec<usize> {
+    assert!(bank.len() >= len);
+
+    // Any digit followed by a larger one should be dropped, as long as enough digits are left
+    let mut can_drop = bank.len() - len;
+    let mut stack: Vec<usize> = Vec::with_capacity(bank.len());
+    for (idx, digit) in bank.iter().enumerate() {
+        while can_drop > 0 && stack.last().is_some_and(|last| bank[*last] < *digit) {
+            stack.pop();
+            can_drop -= 1;
+        }
+        stack.push(idx);
+    }
+
+    stack.truncate(len);
+    stack
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -71,11 +178,77 @@ mod tests {
 
     #[test]
     fn part1_example() {
-        assert_eq!(part1(&parse(EXAMPLE)), "357");
+        assert_eq!(part1(&parse_part1(EXAMPLE).unwrap()), "357");
     }
 
     #[test]
     fn part2_example() {
-        assert_eq!(part2(&parse(EXAMPLE)), "3121910778619");
+        assert_eq!(part2(&parse_part2(EXAMPLE).unwrap()), "3121910778619");
+    }
+
+    #[test]
+    fn largest_subsequence_any_len() {
+        let bank = [8, 1, 8, 1, 8, 1, 9, 1, 1, 1, 1, 2, 1, 1, 1];
+        assert_eq!(largest_subsequence(&bank, 0), []);
+        assert_eq!(largest_subsequence(&bank, 1), [9]);
+        assert_eq!(largest_subsequence(&bank, 3), [9, 2, 1]);
+        assert_eq!(largest_subsequence(&bank, 5), [9, 2, 1, 1, 1]);
+        assert_eq!(
+            largest_subsequence(&bank, 12),
+            [8, 8, 8, 9, 1, 1, 1, 1, 2, 1, 1, 1]
+        );
+        assert_eq!(largest_subsequence(&bank, bank.len()), bank);
+    }
+
+    #[test]
+    fn selection_beyond_u64() {
+        let bank = [8, 1, 8, 1, 8, 1, 9, 1, 1, 1, 1, 2, 1, 1, 1];
+        let selection = select_batteries(&bank, 3);
+        assert_eq!(selection.indices, [6, 11, 12]);
+        assert_eq!(selection.joltage, BigUint::from(921u32));
+
+        let bank = [9; 40];
+        let selection = select_batteries(&bank, 30);
+        assert_eq!(selection.indices, (0..30).collect::<Vec<_>>());
+        assert_eq!(selection.joltage.to_string(), "9".repeat(30));
+        assert_eq!(
+            total_joltage(&[bank.to_vec(), bank.to_vec()], 30).to_string(),
+            format!("1{}8", "9".repeat(29))
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            parse_banks("123\n45\n", 2),
+            Ok(vec![vec![1, 2, 3], vec![4, 5]])
+        );
+        assert_eq!(
+            parse_banks("123\n4/6\n", 2),
+            Err(ParseError {
+                line: 2,
+                column: 2,
+                kind: ParseErrorKind::NotADigit('/')
+            })
+        );
+        assert_eq!(
+            parse_banks("12\r3\n", 2),
+            Err(ParseError {
+                line: 1,
+                column: 3,
+                kind: ParseErrorKind::NotADigit('\r')
+            })
+        );
+        assert_eq!(
+            parse_part2("123456789012\n12345"),
+            Err(ParseError {
+                line: 2,
+                column: 6,
+                kind: ParseErrorKind::BankTooShort {
+                    len: 5,
+                    required: 12
+                }
+            })
+        );
     }
 }
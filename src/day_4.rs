@@ -1,3 +1,5 @@
+use std::{collections::VecDeque, fmt::Display};
+
 use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::Itertools;
 
@@ -13,87 +15,234 @@ fn parse(input: &str) -> Input {
 
 #[aoc(day4, part1)]
 fn part1(input: &Input) -> String {
-    let mut count = 0;
-    for y in 0..input.len() {
-        for x in 0..input.len() {
-            if input[y][x] {
-                // The is a roll here
-
-                let surround_count = (-1..=1)
-                    .cartesian_product(-1..=1)
-                    .filter_map(|offs| {
-                        if offs == (0, 0) {
-                            None
-                        } else {
-                            let x = x.checked_add_signed(offs.0)?;
-                            let y = y.checked_add_signed(offs.1)?;
-
-                            let roll = input.get(y)?.get(x)?;
-
-                            roll.then_some(())
-                        }
-                    })
-                    .count();
+    let count = erode(input, Rule::PUZZLE)
+        .removed_per_wave
+        .first()
+        .copied()
+        .unwrap_or_default();
 
-                if surround_count < 4 {
-                    count += 1;
-                }
+    format!("{count}")
+}
+
+#[aoc(day4, part2)]
+fn part2(input: &Input) -> String {
+    let count: usize = erode(input, Rule::PUZZLE).removed_per_wave.iter().sum();
+
+    format!("{count}")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Neighbourhood {
+    /// All positions within `radius` in both directions (Chebyshev distance), including diagonals
+    Moore,
+    /// All positions within Manhattan distance `radius`
+    VonNeumann,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edges {
+    /// Nothing is outside the grid
+    Bounded,
+    /// The grid wraps around, using the longest row as its width
+    Torus,
+}
+
+/// A roll is accessible if fewer than `threshold` of its neighbours are rolls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rule {
+    pub neighbourhood: Neighbourhood,
+    pub radius: usize,
+    pub threshold: usize,
+    pub edges: Edges,
+}
+
+impl Rule {
+    pub const PUZZLE: Self = Self {
+        neighbourhood: Neighbourhood::Moore,
+        radius: 1,
+        threshold: 4,
+        edges: Edges::Bounded,
+    };
+
+    fn offsets(&self) -> Vec<(isize, isize)> {
+        let radius = isize::try_from(self.radius).expect("Radius too large");
+        (-radius..=radius)
+            .cartesian_product(-radius..=radius)
+            .filter(|offs| *offs != (0, 0))
+            .filter(|(x, y)| match self.neighbourhood {
+                Neighbourhood::Moore => true,
+                Neighbourhood::VonNeumann => x.unsigned_abs() + y.unsigned_abs() <= self.radius,
+            })
+            .collect()
+    }
+}
+
+/// Finds neighbours in a grid according to a [`Rule`]
+struct Neighbours<'a> {
+    edges: Edges,
+    /// On a torus these are reduced to the grid size, so no two of them wrap around to the same position
+    offsets: Vec<(isize, isize)>,
+    grid: &'a [Vec<bool>],
+    width: usize,
+}
+
+impl<'a> Neighbours<'a> {
+    fn new(rule: Rule, grid: &'a [Vec<bool>]) -> Self {
+        let width = grid.iter().map(Vec::len).max().unwrap_or_default();
+        let offsets = match rule.edges {
+            Edges::Bounded => rule.offsets(),
+            Edges::Torus => {
+                // Whether two offsets wrap around to the same position does not depend on the position
+                let width = isize::try_from(width.max(1)).expect("Grid too large");
+                let height = isize::try_from(grid.len().max(1)).expect("Grid too large");
+                rule.offsets()
+                    .into_iter()
+                    .map(|(x, y)| (x.rem_euclid(width), y.rem_euclid(height)))
+                    .filter(|offs| *offs != (0, 0))
+                    .unique()
+                    .collect()
             }
+        };
+
+        Self {
+            edges: rule.edges,
+            offsets,
+            grid,
+            width,
         }
     }
 
-    format!("{count}")
+    /// All distinct positions in the grid around (x, y), not including (x, y) itself.
+    fn of(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.offsets.iter().filter_map(move |offs| {
+            let (x, y) = match self.edges {
+                Edges::Bounded => (x.checked_add_signed(offs.0)?, y.checked_add_signed(offs.1)?),
+                Edges::Torus => (
+                    wrap(x, offs.0, self.width),
+                    wrap(y, offs.1, self.grid.len()),
+                ),
+            };
+
+            self.grid.get(y)?.get(x)?;
+
+            Some((x, y))
+        })
+    }
 }
 
-#[aoc(day4, part2)]
-fn part2(input: &Input) -> String {
-    let mut input = input.clone();
-    let mut count = 0;
-
-    loop {
-        let mut changed = false;
-
-        for y in 0..input.len() {
-            for x in 0..input.len() {
-                if input[y][x] {
-                    // The is a roll here
-
-                    let surround_count = (-1..=1)
-                        .cartesian_product(-1..=1)
-                        .filter_map(|offs| {
-                            if offs == (0, 0) {
-                                None
-                            } else {
-                                let x = x.checked_add_signed(offs.0)?;
-                                let y = y.checked_add_signed(offs.1)?;
-
-                                let roll = input.get(y)?.get(x)?;
-
-                                roll.then_some(())
-                            }
-                        })
-                        .count();
-
-                    if surround_count < 4 {
-                        count += 1;
-                        input[y][x] = false;
-                        changed = true;
-                    }
-                }
+fn wrap(pos: usize, offs: isize, len: usize) -> usize {
+    let len = isize::try_from(len).expect("Grid too large");
+    let pos = isize::try_from(pos).expect("Grid too large");
+    usize::try_from((pos + offs).rem_euclid(len)).expect("rem_euclid is never negative")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cell {
+    Empty,
+    /// A roll which is never accessible
+    Roll,
+    /// A roll which became accessible in `wave` (starting at 1), once all rolls of the previous waves were removed
+    Removed {
+        wave: usize,
+    },
+}
+
+/// How a warehouse erodes when accessible rolls are removed until none are left.
+/// Its [`Display`] implementation prints the grid with the wave numbers in base 36 (or `+` above that).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Erosion {
+    pub cells: Vec<Vec<Cell>>,
+    /// The first entry is wave 1
+    pub removed_per_wave: Vec<usize>,
+}
+
+impl Display for Erosion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in &self.cells {
+            for cell in row {
+                let c = match cell {
+                    Cell::Empty => '.',
+                    Cell::Roll => '@',
+                    Cell::Removed { wave } => u32::try_from(*wave)
+                        .ok()
+                        .and_then(|wave| char::from_digit(wave, 36))
+                        .unwrap_or('+'),
+                };
+                write!(f, "{c}")?;
             }
+            writeln!(f)?;
         }
+        Ok(())
+    }
+}
+
+/// Removes rolls accessible according to `rule` in waves until none are left.
+///
+/// # Panics
+/// If the radius or grid size do not fit in an [`isize`].
+#[must_use]
+pub fn erode(grid: &[Vec<bool>], rule: Rule) -> Erosion {
+    let finder = Neighbours::new(rule, grid);
+    let mut cells: Vec<Vec<Cell>> = grid
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|roll| if *roll { Cell::Roll } else { Cell::Empty })
+                .collect()
+        })
+        .collect();
+    let mut removed_per_wave = vec![];
+
+    // Removing a roll can only make its neighbours accessible, so only those need to be looked at again
+    let mut grid = grid.to_vec();
+    let mut neighbour_counts: Vec<Vec<usize>> = grid
+        .iter()
+        .enumerate()
+        .map(|(y, row)| {
+            (0..row.len())
+                .map(|x| finder.of(x, y).filter(|(x, y)| grid[*y][*x]).count())
+                .collect()
+        })
+        .collect();
+    let mut queue: VecDeque<(usize, usize, usize)> = grid
+        .iter()
+        .enumerate()
+        .flat_map(|(y, row)| row.iter().enumerate().map(move |(x, roll)| (x, y, *roll)))
+        .filter(|(x, y, roll)| *roll && neighbour_counts[*y][*x] < rule.threshold)
+        .map(|(x, y, _)| (x, y, 1))
+        .collect();
+
+    // Since the queue is processed in order, a roll made accessible by a roll of wave n is part of wave n + 1
+    while let Some((x, y, wave)) = queue.pop_front() {
+        // Every roll is only queued once, when it becomes accessible
+        grid[y][x] = false;
+        cells[y][x] = Cell::Removed { wave };
+        if removed_per_wave.len() < wave {
+            removed_per_wave.push(0);
+        }
+        removed_per_wave[wave - 1] += 1;
 
-        if !changed {
-            break;
+        // Neighbourhoods are symmetric, so these are exactly the rolls which had this one as a neighbour
+        for (x, y) in finder.of(x, y) {
+            if grid[y][x] {
+                neighbour_counts[y][x] -= 1;
+                if neighbour_counts[y][x] + 1 == rule.threshold {
+                    queue.push_back((x, y, wave + 1));
+                }
+            }
         }
     }
 
-    format!("{count}")
+    Erosion {
+        cells,
+        removed_per_wave,
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::util::TestRng;
 
     const EXAMPLE: &str = include_str!("../example/day4.txt");
 
@@ -106,4 +255,130 @@ mod tests {
     fn part2_example() {
         assert_eq!(part2(&parse(EXAMPLE)), "43");
     }
+
+    const WIDE: &str = "..@@.@@@@.\n@@@.@.@.@@\n@@@@@.@.@@\n@.@@@@..@.\n";
+    const TALL: &str = ".@@@\n.@@.\n@@@@\n@.@@\n.@@@\n@..@\n@@@.\n@...\n@@@@\n.@@.\n";
+    const RAGGED: &str = "@@@@@@\n@@@\n@@@@@@@@\n@\n@@@@@\n";
+
+    #[test]
+    fn part1_not_square() {
+        assert_eq!(part1(&parse(WIDE)), "10");
+        assert_eq!(part1(&parse(TALL)), "10");
+        assert_eq!(part1(&parse(RAGGED)), "14");
+    }
+
+    #[test]
+    fn part2_not_square() {
+        assert_eq!(part2(&parse(WIDE)), "27");
+        assert_eq!(part2(&parse(TALL)), "27");
+        assert_eq!(part2(&parse(RAGGED)), "23");
+    }
+
+    #[test]
+    fn part2_matches_rescanning() {
+        // The original solution, rescanning the whole grid until nothing changes
+        fn rescan(input: &Input) -> usize {
+            let mut input = input.clone();
+            let mut count = 0;
+            loop {
+                let mut changed = false;
+                for y in 0..input.len() {
+                    for x in 0..input[y].len() {
+                        if input[y][x]
+                            && Neighbours::new(Rule::PUZZLE, &input)
+                                .of(x, y)
+                                .filter(|(x, y)| input[*y][*x])
+                                .count()
+                                < 4
+                        {
+                            count += 1;
+                            input[y][x] = false;
+                            changed = true;
+                        }
+                    }
+                }
+                if !changed {
+                    return count;
+                }
+            }
+        }
+
+        let mut rng = TestRng::new(12345);
+        let grid: Input = (0..60)
+            .map(|y| (0..45 + y % 7).map(|_| rng.below(10) < 7).collect())
+            .collect();
+
+        assert_eq!(part2(&grid), rescan(&grid).to_string());
+    }
+
+    #[test]
+    fn erosion_waves() {
+        let input = parse(EXAMPLE);
+        let erosion = erode(&input, Rule::PUZZLE);
+
+        assert_eq!(erosion.removed_per_wave.first(), Some(&13));
+        assert_eq!(erosion.removed_per_wave.iter().sum::<usize>(), 43);
+
+        let display = erosion.to_string();
+        assert_eq!(display.lines().count(), input.len());
+        assert_eq!(display.lines().next(), Some("..11.1121."));
+        assert_eq!(
+            display.chars().filter(|c| *c == '1').count(),
+            erosion.removed_per_wave[0]
+        );
+    }
+
+    #[test]
+    fn other_rules() {
+        let plus = parse(".@.\n@@@\n.@.\n");
+        let von_neumann = Rule {
+            neighbourhood: Neighbourhood::VonNeumann,
+            ..Rule::PUZZLE
+        };
+        // The centre has 4 neighbours, so it only becomes accessible in the second wave
+        let erosion = erode(&plus, von_neumann);
+        assert_eq!(erosion.removed_per_wave, [4, 1]);
+        assert_eq!(erosion.to_string(), ".1.\n121\n.1.\n");
+        let everything = Rule {
+            threshold: 5,
+            ..von_neumann
+        };
+        assert_eq!(erode(&plus, everything).removed_per_wave, [5]);
+
+        // On a torus every roll of a full grid has 8 neighbours
+        let full = parse("@@@@\n@@@@\n@@@@\n@@@@\n");
+        let torus = Rule {
+            edges: Edges::Torus,
+            ..Rule::PUZZLE
+        };
+        assert_eq!(erode(&full, torus).removed_per_wave, []);
+        // On a 2x2 torus all offsets wrap around to the 3 other rolls
+        let tiny = parse("@@\n@@\n");
+        assert_eq!(erode(&tiny, torus).removed_per_wave, [4]);
+        assert_eq!(
+            erode(&full, Rule::PUZZLE).removed_per_wave.first(),
+            Some(&4)
+        );
+
+        // With radius 2 a corner only sees the other 8 rolls of its 3x3 corner, so only the corners are below 10.
+        // The centre sees all other 24 rolls, so with a threshold of 25 everything goes at once
+        let big = parse(&"@@@@@\n".repeat(5));
+        let radius_2 = Rule {
+            radius: 2,
+            threshold: 10,
+            ..Rule::PUZZLE
+        };
+        assert_eq!(erode(&big, radius_2).removed_per_wave.first(), Some(&4));
+        assert_eq!(
+            erode(
+                &big,
+                Rule {
+                    threshold: 25,
+                    ..radius_2
+                }
+            )
+            .removed_per_wave,
+            [25]
+        );
+    }
 }
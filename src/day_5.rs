@@ -1,6 +1,6 @@
-use core::slice::GetDisjointMutIndex;
 use std::{
     cmp::{max, min},
+    collections::{BTreeMap, HashMap},
     ops::RangeInclusive,
 };
 
@@ -36,11 +36,11 @@ fn parse(input: &str) -> Input {
 
 #[aoc(day5, part1)]
 fn part1(input: &Input) -> String {
-    let count = input
-        .1
-        .iter()
-        .copied()
-        .filter(|id| input.0.iter().any(|range| range.contains(id)))
+    let fresh = input.0.iter().cloned().collect::<IntervalSet>();
+    let count = fresh
+        .contains_batch(&input.1)
+        .into_iter()
+        .filter(|is_fresh| *is_fresh)
         .count();
     format!("{count}")
 }
@@ -48,145 +48,453 @@ fn part1(input: &Input) -> String {
 #[aoc(day5, part2)]
 fn part2(input: &Input) -> String {
     // The silly solution would be to just count, but that of course does not scale
-    let mut input = input.0.clone();
-    de_overlap(&mut input);
-
-    let count: usize = input.iter().map(len).sum();
+    let count = input
+        .0
+        .iter()
+        .cloned()
+        .collect::<IntervalSet>()
+        .covered_len();
 
     format!("{count}")
 }
 
-fn len(range: &RangeInclusive<usize>) -> usize {
-    range
-        .end()
-        .checked_sub(*range.start())
-        .unwrap_or_else(|| panic!("{range:?}"))
-        + 1
+/// A set of integers, stored as sorted ranges which neither overlap nor touch.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntervalSet {
+    ranges: Vec<RangeInclusive<usize>>,
 }
 
-fn de_overlap(input: &mut Vec<RangeInclusive<usize>>) {
-    let mut idx = 0;
-
-    // I am not super happy using loops and while loops here
-    loop {
-        let mut prev_idx = 0;
-        while prev_idx < idx {
-            let current = &input[idx];
-            assert!(
-                current.start() <= current.end(),
-                "{} <= {}",
-                current.start(),
-                current.end()
-            );
-            let prev = &input[prev_idx];
-
-            if current.is_overlapping(prev) {
-                if prev.start() <= current.start() {
-                    // Shorten current
-                    let new_start = *max(prev.end(), current.start()) + 1;
-
-                    let new = new_start..=*current.end();
-
-                    assert!(!new.is_overlapping(prev));
-
-                    if *new.start() > *new.end() {
-                        input.remove(idx);
-                        idx -= 1;
-                    } else {
-                        assert!(new.start() <= new.end(), "{} <= {}", new.start(), new.end());
-                        input[idx] = new;
-                    }
-                } else if prev.end() >= current.end() {
-                    // Shorten current
-                    let new_end = *min(prev.start(), current.end()) - 1;
-
-                    let new = *current.start()..=new_end;
-
-                    assert!(!new.is_overlapping(prev));
-
-                    if *new.start() > *new.end() {
-                        input.remove(idx);
-                        idx -= 1;
-                    } else {
-                        assert!(new.start() <= new.end(), "{} <= {}", new.start(), new.end());
-                        input[idx] = new;
-                    }
-                } else {
-                    // Split current
-                    let lower_new = if current.start() == prev.start() {
-                        None
-                    } else {
-                        Some(*current.start()..=(*prev.start() - 1))
-                    };
-                    let upper_new = if prev.end() == current.end() {
-                        None
-                    } else {
-                        Some((*prev.end() + 1)..=*current.end())
-                    };
-
-                    if let Some(upper_new) = &upper_new {
-                        assert!(!upper_new.is_overlapping(prev));
-                        assert!(
-                            upper_new.start() <= upper_new.end(),
-                            "{} <= {}",
-                            upper_new.start(),
-                            upper_new.end()
-                        );
-                    }
-
-                    if let Some(lower_new) = &lower_new {
-                        assert!(!lower_new.is_overlapping(prev));
-
-                        assert!(
-                            lower_new.start() <= lower_new.end(),
-                            "{} <= {}",
-                            lower_new.start(),
-                            lower_new.end()
-                        );
-                    }
-
-                    if let Some(lower_new) = &lower_new
-                        && let Some(upper_new) = &upper_new
-                    {
-                        assert!(!lower_new.is_overlapping(upper_new));
-                    }
-
-                    let res = [current.clone()];
-                    itertools::assert_equal(
-                        input.splice(idx..=idx, [lower_new, upper_new].into_iter().flatten()),
-                        res,
-                    );
+/// Whether the ranges share at least one value
+fn overlaps(a: &RangeInclusive<usize>, b: &RangeInclusive<usize>) -> bool {
+    !a.is_empty() && !b.is_empty() && max(a.start(), b.start()) <= min(a.end(), b.end())
+}
+
+/// Whether one range starts right after the other ends, without a gap or overlap
+fn is_adjacent(a: &RangeInclusive<usize>, b: &RangeInclusive<usize>) -> bool {
+    !a.is_empty()
+        && !b.is_empty()
+        && (a.end().checked_add(1) == Some(*b.start())
+            || b.end().checked_add(1) == Some(*a.start()))
+}
+
+/// Whether the union of the ranges is a single range
+fn can_merge(a: &RangeInclusive<usize>, b: &RangeInclusive<usize>) -> bool {
+    overlaps(a, b) || is_adjacent(a, b)
+}
+
+impl FromIterator<RangeInclusive<usize>> for IntervalSet {
+    fn from_iter<I: IntoIterator<Item = RangeInclusive<usize>>>(iter: I) -> Self {
+        let mut ranges = iter
+            .into_iter()
+            .filter(|range| !range.is_empty())
+            .collect_vec();
+        ranges.sort_unstable_by_key(|range| *range.start());
+
+        let mut merged: Vec<RangeInclusive<usize>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if can_merge(last, &range) => {
+                    *last = *last.start()..=max(*last.end(), *range.end());
                 }
+                _ => merged.push(range),
             }
-
-            prev_idx += 1;
         }
 
-        idx += 1;
-        if idx == input.len() {
-            break;
+        Self { ranges: merged }
+    }
+}
+
+impl IntervalSet {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { ranges: vec![] }
+    }
+
+    /// The sorted, disjoint ranges making up this set
+    #[must_use]
+    pub fn ranges(&self) -> &[RangeInclusive<usize>] {
+        &self.ranges
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    #[must_use]
+    pub fn contains(&self, val: usize) -> bool {
+        self.range_containing(val).is_some()
+    }
+
+    /// The range of this set `val` falls into
+    #[must_use]
+    pub fn range_containing(&self, val: usize) -> Option<&RangeInclusive<usize>> {
+        let idx = self.ranges.partition_point(|range| *range.end() < val);
+        self.ranges.get(idx).filter(|range| *range.start() <= val)
+    }
+
+    /// Checks every value, returning the results in the same order.
+    /// Faster than calling [`Self::contains`] for each value when there are many.
+    #[must_use]
+    pub fn contains_batch(&self, vals: &[usize]) -> Vec<bool> {
+        let mut order = (0..vals.len()).collect_vec();
+        order.sort_unstable_by_key(|idx| vals[*idx]);
+
+        let mut ret = vec![false; vals.len()];
+        let mut ranges = self.ranges.iter().peekable();
+        for idx in order {
+            let val = vals[idx];
+            // Values are sorted, so any range ending before this value can never contain a later one
+            while ranges.next_if(|range| *range.end() < val).is_some() {}
+            ret[idx] = ranges.peek().is_some_and(|range| *range.start() <= val);
         }
+        ret
     }
 
-    assert_eq!(
-        input
+    /// The number of integers in this set. This is a [`u128`], since a set covering all of [`usize`] has one more.
+    #[must_use]
+    pub fn covered_len(&self) -> u128 {
+        self.ranges
             .iter()
-            .enumerate()
-            .cartesian_product(input.iter().enumerate())
-            .find(|((a_idx, a), (b_idx, b))| {
-                if a_idx == b_idx {
-                    false
-                } else {
-                    a.is_overlapping(b)
-                }
-            }),
-        None
-    );
+            .map(|range| (range.end() - range.start()) as u128 + 1)
+            .sum()
+    }
+
+    pub fn insert(&mut self, range: RangeInclusive<usize>) {
+        if range.is_empty() {
+            return;
+        }
+
+        // All ranges in first..last overlap or touch the new one
+        let first = self.ranges.partition_point(|existing| {
+            existing.end() < range.start() && !can_merge(existing, &range)
+        });
+        let last = self.ranges.partition_point(|existing| {
+            existing.start() <= range.end() || can_merge(existing, &range)
+        });
+
+        let (lo, hi) = (*range.start(), *range.end());
+        let start = self
+            .ranges
+            .get(first..last)
+            .and_then(<[_]>::first)
+            .map_or(lo, |existing| min(*existing.start(), lo));
+        let end = self
+            .ranges
+            .get(first..last)
+            .and_then(<[_]>::last)
+            .map_or(hi, |existing| max(*existing.end(), hi));
+
+        self.ranges
+            .splice(first..last, std::iter::once(start..=end));
+    }
+
+    pub fn remove(&mut self, range: &RangeInclusive<usize>) {
+        if range.is_empty() {
+            return;
+        }
+
+        // All ranges in first..last overlap the removed one
+        let first = self
+            .ranges
+            .partition_point(|existing| existing.end() < range.start());
+        let last = self
+            .ranges
+            .partition_point(|existing| existing.start() <= range.end());
+        if first == last {
+            return;
+        }
+
+        let before = (self.ranges[first].start() < range.start()).then(|| {
+            let before_end = range.start() - 1;
+            *self.ranges[first].start()..=before_end
+        });
+        let after = (self.ranges[last - 1].end() > range.end())
+            .then(|| range.end() + 1..=*self.ranges[last - 1].end());
+
+        self.ranges
+            .splice(first..last, [before, after].into_iter().flatten());
+    }
+
+    #[must_use]
+    pub fn union(&self, other: &Self) -> Self {
+        self.ranges.iter().chain(&other.ranges).cloned().collect()
+    }
+
+    #[must_use]
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = vec![];
+        let (mut ours, mut theirs) = (
+            self.ranges.iter().peekable(),
+            other.ranges.iter().peekable(),
+        );
+
+        while let (Some(a), Some(b)) = (ours.peek(), theirs.peek()) {
+            let start = max(a.start(), b.start());
+            let end = min(a.end(), b.end());
+            if start <= end {
+                ranges.push(*start..=*end);
+            }
+
+            // The range ending first cannot intersect anything else
+            if a.end() < b.end() {
+                ours.next();
+            } else {
+                theirs.next();
+            }
+        }
+
+        Self { ranges }
+    }
+
+    #[must_use]
+    pub fn difference(&self, other: &Self) -> Self {
+        match (self.ranges.first(), self.ranges.last()) {
+            (Some(first), Some(last)) => {
+                self.intersection(&other.complement(*first.start()..=*last.end()))
+            }
+            _ => Self::new(),
+        }
+    }
+
+    /// All integers in `bounds` which are not in this set.
+    #[must_use]
+    pub fn complement(&self, bounds: RangeInclusive<usize>) -> Self {
+        if bounds.is_empty() {
+            return Self::new();
+        }
+
+        let mut ranges = vec![];
+        // The first integer which might still be missing from the set, None once we are past the bounds
+        let mut next_missing = Some(*bounds.start());
+        for range in &self.ranges {
+            let Some(missing) = next_missing else {
+                break;
+            };
+            if *range.start() > *bounds.end() {
+                break;
+            }
+            if *range.start() > missing {
+                let gap_end = range.start() - 1;
+                ranges.push(missing..=gap_end);
+            }
+            if *range.end() >= missing {
+                next_missing = range.end().checked_add(1);
+            }
+        }
+        if let Some(missing) = next_missing
+            && missing <= *bounds.end()
+        {
+            ranges.push(missing..=*bounds.end());
+        }
+
+        Self { ranges }
+    }
+}
+
+/// Why an id is fresh.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdExplanation {
+    pub id: usize,
+    /// Indices of all ranges containing the id, which are also their line index in the input
+    pub ranges: Vec<usize>,
+    /// `None` if the id is spoiled
+    pub merged: Option<RangeInclusive<usize>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation {
+    /// In the same order as the ids
+    pub ids: Vec<IdExplanation>,
+    /// Ids not contained in any range, in the same order as the ids
+    pub spoiled: Vec<usize>,
+}
+
+/// Finds out which ranges make each of the `ids` fresh.
+#[must_use]
+pub fn explain(ranges: &[RangeInclusive<usize>], ids: &[usize]) -> Explanation {
+    let merged = ranges.iter().cloned().collect::<IntervalSet>();
+
+    // Sweep over the ids in ascending order, keeping track of which ranges contain the current one
+    let mut by_start = (0..ranges.len())
+        .filter(|idx| !ranges[*idx].is_empty())
+        .sorted_unstable_by_key(|idx| *ranges[*idx].start())
+        .peekable();
+    let mut order = (0..ids.len()).collect_vec();
+    order.sort_unstable_by_key(|idx| ids[*idx]);
+
+    let mut containing = vec![vec![]; ids.len()];
+    let mut active: Vec<usize> = vec![];
+    for idx in order {
+        let id = ids[idx];
+        while let Some(range_idx) = by_start.next_if(|range_idx| *ranges[*range_idx].start() <= id)
+        {
+            active.push(range_idx);
+        }
+        active.retain(|range_idx| *ranges[*range_idx].end() >= id);
+
+        containing[idx] = active.iter().copied().sorted_unstable().collect();
+    }
+
+    let explanations = ids
+        .iter()
+        .zip(containing)
+        .map(|(id, ranges)| IdExplanation {
+            id: *id,
+            ranges,
+            merged: merged.range_containing(*id).cloned(),
+        })
+        .collect_vec();
+    let spoiled = explanations
+        .iter()
+        .filter(|explanation| explanation.ranges.is_empty())
+        .map(|explanation| explanation.id)
+        .collect();
+
+    Explanation {
+        ids: explanations,
+        spoiled,
+    }
+}
+
+/// Freshness ranges which can change over time, for a fixed list of ids.
+#[derive(Debug, Clone, Default)]
+pub struct FreshnessDb {
+    /// Sorted
+    ids: Vec<usize>,
+    /// How often each range was added
+    ranges: HashMap<RangeInclusive<usize>, usize>,
+    /// How many ranges contain each value, from the key up to the next key
+    overlap_counts: BTreeMap<usize, usize>,
+    coverage: IntervalSet,
+    fresh: usize,
+}
+
+impl FreshnessDb {
+    pub fn new(ids: impl IntoIterator<Item = usize>) -> Self {
+        Self {
+            ids: ids.into_iter().sorted_unstable().collect(),
+            ..Default::default()
+        }
+    }
+
+    /// All values contained in any range
+    #[must_use]
+    pub const fn coverage(&self) -> &IntervalSet {
+        &self.coverage
+    }
+
+    /// The number of ids contained in any range, counting duplicate ids multiple times
+    #[must_use]
+    pub const fn fresh_count(&self) -> usize {
+        self.fresh
+    }
+
+    pub fn add_range(&mut self, range: RangeInclusive<usize>) {
+        if range.is_empty() {
+            return;
+        }
+
+        for segment in self.change_overlap_counts(&range, |count| {
+            *count += 1;
+            *count == 1
+        }) {
+            self.fresh += self.count_ids(&segment);
+            self.coverage.insert(segment);
+        }
+        *self.ranges.entry(range).or_default() += 1;
+    }
+
+    /// Removes a single copy of `range`. Returns `false` if it was never added.
+    pub fn remove_range(&mut self, range: &RangeInclusive<usize>) -> bool {
+        let Some(added) = self.ranges.get_mut(range) else {
+            return false;
+        };
+        *added -= 1;
+        if *added == 0 {
+            self.ranges.remove(range);
+        }
+
+        for segment in self.change_overlap_counts(range, |count| {
+            *count -= 1;
+            *count == 0
+        }) {
+            self.fresh -= self.count_ids(&segment);
+            self.coverage.remove(&segment);
+        }
+        true
+    }
+
+    /// Applies `change` to the overlap count of every value in `range`.
+    /// Returns the segments for which `change` returned true.
+    fn change_overlap_counts(
+        &mut self,
+        range: &RangeInclusive<usize>,
+        change: impl Fn(&mut usize) -> bool,
+    ) -> Vec<RangeInclusive<usize>> {
+        let after = range.end().checked_add(1);
+        self.split_segment(*range.start());
+        if let Some(after) = after {
+            self.split_segment(after);
+        }
+
+        let starts = self
+            .overlap_counts
+            .range(range.clone())
+            .map(|(start, _)| *start)
+            .collect_vec();
+        let last = *range.end();
+        let mut changed = vec![];
+        for (idx, start) in starts.iter().enumerate() {
+            let end = starts.get(idx + 1).map_or(last, |next| next - 1);
+            if change(self.overlap_counts.get_mut(start).expect("Segment exists")) {
+                changed.push(*start..=end);
+            }
+        }
+
+        // Every count inside the range changed the same way, so only the borders can be merged
+        self.merge_segment(*range.start());
+        if let Some(after) = after {
+            self.merge_segment(after);
+        }
+
+        changed
+    }
+
+    fn segment_count(&self, at: usize) -> usize {
+        self.overlap_counts
+            .range(..=at)
+            .next_back()
+            .map_or(0, |(_, count)| *count)
+    }
+
+    /// Makes sure a segment starts at `at`
+    fn split_segment(&mut self, at: usize) {
+        let count = self.segment_count(at);
+        self.overlap_counts.entry(at).or_insert(count);
+    }
+
+    /// Removes the segment starting at `at`, if it has the same count as the one before it
+    fn merge_segment(&mut self, at: usize) {
+        let before = at
+            .checked_sub(1)
+            .map_or(0, |before| self.segment_count(before));
+        if self.overlap_counts.get(&at) == Some(&before) {
+            self.overlap_counts.remove(&at);
+        }
+    }
+
+    fn count_ids(&self, range: &RangeInclusive<usize>) -> usize {
+        let first = self.ids.partition_point(|id| id < range.start());
+        let last = self.ids.partition_point(|id| id <= range.end());
+        last - first
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::util::TestRng;
 
     const EXAMPLE: &str = include_str!("../example/day5.txt");
 
@@ -199,4 +507,209 @@ mod tests {
     fn part2_example() {
         assert_eq!(part2(&parse(EXAMPLE)), "14");
     }
+
+    #[test]
+    fn overlap_and_adjacency() {
+        assert!(overlaps(&(3..=5), &(5..=7)));
+        assert!(overlaps(&(3..=10), &(5..=7)));
+        assert!(overlaps(&(5..=7), &(3..=10)));
+        assert!(!overlaps(&(3..=5), &(6..=7)));
+        assert!(!overlaps(&(3..=5), &RangeInclusive::new(4, 2)));
+
+        assert!(is_adjacent(&(3..=5), &(6..=7)));
+        assert!(is_adjacent(&(6..=7), &(3..=5)));
+        assert!(!is_adjacent(&(3..=5), &(5..=7)));
+        assert!(!is_adjacent(&(3..=5), &(7..=9)));
+        assert!(!is_adjacent(&(0..=usize::MAX), &(0..=0)));
+
+        assert!(can_merge(&(3..=5), &(6..=7)));
+        assert!(can_merge(&(3..=5), &(4..=4)));
+        assert!(!can_merge(&(3..=5), &(7..=7)));
+    }
+
+    fn to_bools(set: &IntervalSet, size: usize) -> Vec<bool> {
+        (0..size)
+            .map(|val| set.ranges().iter().any(|range| range.contains(&val)))
+            .collect()
+    }
+
+    #[test]
+    fn interval_set_operations() {
+        const SIZE: usize = 64;
+        let mut rng = TestRng::new(42);
+        let mut random_set = || {
+            (0..6)
+                .map(|_| {
+                    let start = rng.below(SIZE);
+                    start..=min(start + rng.below(12), SIZE - 1)
+                })
+                .collect_vec()
+        };
+
+        for _ in 0..100 {
+            let a_ranges = random_set();
+            let b_ranges = random_set();
+            let a: IntervalSet = a_ranges.iter().cloned().collect();
+            let b: IntervalSet = b_ranges.iter().cloned().collect();
+
+            let mut inserted = IntervalSet::new();
+            for range in &a_ranges {
+                inserted.insert(range.clone());
+            }
+            assert_eq!(inserted, a);
+            for range in &b_ranges {
+                inserted.remove(range);
+            }
+            assert_eq!(inserted, a.difference(&b));
+
+            for set in [&a, &b] {
+                assert!(
+                    set.ranges()
+                        .windows(2)
+                        .all(|w| w[0].end() + 1 < *w[1].start())
+                );
+            }
+
+            let a_bools = to_bools(&a, SIZE);
+            let b_bools = to_bools(&b, SIZE);
+            let expected = |op: fn(bool, bool) -> bool| {
+                a_bools
+                    .iter()
+                    .zip(&b_bools)
+                    .map(|(a, b)| op(*a, *b))
+                    .collect_vec()
+            };
+
+            assert_eq!(to_bools(&a.union(&b), SIZE), expected(|a, b| a || b));
+            assert_eq!(to_bools(&a.intersection(&b), SIZE), expected(|a, b| a && b));
+            assert_eq!(to_bools(&a.difference(&b), SIZE), expected(|a, b| a && !b));
+            assert_eq!(
+                to_bools(&a.complement(5..=50), SIZE),
+                (0..SIZE)
+                    .map(|val| (5..=50).contains(&val) && !a_bools[val])
+                    .collect_vec()
+            );
+            assert_eq!(
+                a.covered_len(),
+                u128::try_from(a_bools.iter().filter(|covered| **covered).count()).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn interval_set_extremes() {
+        let set: IntervalSet = [0..=5, usize::MAX - 5..=usize::MAX, 6..=10]
+            .into_iter()
+            .collect();
+        assert_eq!(set.ranges(), [0..=10, usize::MAX - 5..=usize::MAX]);
+        assert_eq!(set.covered_len(), 17);
+        assert_eq!(
+            set.complement(0..=usize::MAX).ranges(),
+            [RangeInclusive::new(11, usize::MAX - 6)]
+        );
+        assert!(set.complement(3..=8).is_empty());
+
+        let full: IntervalSet = std::iter::once(0..=usize::MAX).collect();
+        assert_eq!(full.covered_len(), usize::MAX as u128 + 1);
+        assert_eq!(set.union(&full), full);
+    }
+
+    #[test]
+    fn membership() {
+        let set: IntervalSet = [3..=5, 10..=14, 16..=20, 12..=18].into_iter().collect();
+        let ids = (0..25).rev().collect_vec();
+        let expected = ids
+            .iter()
+            .map(|id| (3..=5).contains(id) || (10..=20).contains(id))
+            .collect_vec();
+
+        assert_eq!(
+            ids.iter().map(|id| set.contains(*id)).collect_vec(),
+            expected
+        );
+        assert_eq!(set.contains_batch(&ids), expected);
+        assert_eq!(set.contains_batch(&[]), []);
+        assert!(!IntervalSet::new().contains(0));
+    }
+
+    #[test]
+    fn explain_example() {
+        let (ranges, ids) = parse(EXAMPLE);
+        let explanation = explain(&ranges, &ids);
+
+        assert_eq!(explanation.spoiled, [1, 8, 32]);
+        assert_eq!(
+            explanation.ids[1],
+            IdExplanation {
+                id: 5,
+                ranges: vec![0],
+                merged: Some(3..=5),
+            }
+        );
+        assert_eq!(
+            explanation.ids[4],
+            IdExplanation {
+                id: 17,
+                ranges: vec![2, 3],
+                merged: Some(10..=20),
+            }
+        );
+        assert_eq!(
+            explanation.ids[5],
+            IdExplanation {
+                id: 32,
+                ranges: vec![],
+                merged: None,
+            }
+        );
+    }
+
+    #[test]
+    fn freshness_db_matches_rebuilding() {
+        let ids = (0..80).map(|id| id * 7 % 71).collect_vec();
+        let mut db = FreshnessDb::new(ids.iter().copied());
+        let mut added: Vec<RangeInclusive<usize>> = vec![];
+
+        let mut rng = TestRng::new(7);
+
+        for _ in 0..300 {
+            if !added.is_empty() && rng.below(3) == 0 {
+                let range = added.swap_remove(rng.below(added.len()));
+                assert!(db.remove_range(&range));
+            } else {
+                let start = rng.below(75);
+                let range = start..=start + rng.below(10);
+                added.push(range.clone());
+                db.add_range(range);
+            }
+
+            let expected: IntervalSet = added.iter().cloned().collect();
+            assert_eq!(db.coverage(), &expected);
+            assert_eq!(
+                db.fresh_count(),
+                ids.iter().filter(|id| expected.contains(**id)).count()
+            );
+        }
+
+        assert!(!db.remove_range(&(100..=200)));
+        for range in added {
+            assert!(db.remove_range(&range));
+        }
+        assert!(db.coverage().is_empty());
+        assert_eq!(db.fresh_count(), 0);
+        assert!(db.overlap_counts.is_empty());
+    }
+
+    #[test]
+    fn freshness_db_extremes() {
+        let mut db = FreshnessDb::new([0, usize::MAX]);
+        db.add_range(0..=usize::MAX);
+        db.add_range(5..=usize::MAX);
+        assert_eq!(db.fresh_count(), 2);
+        assert!(db.remove_range(&(0..=usize::MAX)));
+        assert_eq!(db.fresh_count(), 1);
+        assert_eq!(db.coverage().ranges(), [RangeInclusive::new(5, usize::MAX)]);
+        db.add_range(0..=usize::MAX);
+        assert_eq!(db.coverage().covered_len(), usize::MAX as u128 + 1);
+    }
 }
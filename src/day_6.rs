@@ -1,160 +1,499 @@
-use std::mem;
+use std::{
+    cmp::{max, min},
+    error::Error,
+    fmt::Display,
+    ops::RangeInclusive,
+};
 
 use aoc_runner_derive::{aoc, aoc_generator};
-use itertools::{Itertools, Position};
+use itertools::Itertools;
+use num_bigint::BigUint;
 
-#[derive(Debug, Clone, Copy)]
-enum Kind {
+/// The operation of a problem. All operations fold the numbers from the first to the last,
+/// so `-` with 10, 3, 2 is `(10 - 3) - 2` and `^` with 2, 3, 2 is `(2 ^ 3) ^ 2`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
     Add,
     Mul,
+    Sub,
+    /// Integer division, rounding down
+    Div,
+    Min,
+    Max,
+    Pow,
 }
 
-#[derive(Debug)]
-struct Problem {
-    data: Vec<u64>,
-    kind: Kind,
+impl Kind {
+    pub const ALL: [Self; 7] = [
+        Self::Add,
+        Self::Mul,
+        Self::Sub,
+        Self::Div,
+        Self::Min,
+        Self::Max,
+        Self::Pow,
+    ];
+
+    #[must_use]
+    pub const fn symbol(self) -> char {
+        match self {
+            Self::Add => '+',
+            Self::Mul => '*',
+            Self::Sub => '-',
+            Self::Div => '/',
+            Self::Min => '<',
+            Self::Max => '>',
+            Self::Pow => '^',
+        }
+    }
+
+    #[must_use]
+    pub fn from_symbol(symbol: char) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.symbol() == symbol)
+    }
 }
 
-type Input = Vec<Problem>;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvalError {
+    DivisionByZero,
+    /// The result is too large to calculate (see [`Problem::MAX_BITS`]),
+    /// or larger than [`u64::MAX`] where a big integer is not possible
+    Overflow,
+    /// The result is negative
+    Underflow,
+    /// Only addition and multiplication have a result without any numbers
+    NoNumbers,
+}
 
-#[aoc_generator(day6, part1)]
-fn parse_part1(input: &str) -> Input {
-    let columns: usize = input.lines().next().unwrap().split_whitespace().count();
-    let mut ret = vec![(vec![], None); columns];
-
-    for (column, v) in input
-        .lines()
-        .flat_map(|line| line.split_whitespace().enumerate())
-    {
-        if let Ok(v) = v.parse() {
-            ret[column].0.push(v);
-        } else {
-            match v {
-                "+" => ret[column].1 = Some(Kind::Add),
-                "*" => ret[column].1 = Some(Kind::Mul),
-                _ => unreachable!("{}", v),
-            }
+impl Display for EvalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DivisionByZero => write!(f, "division by zero"),
+            Self::Overflow => write!(f, "result too large"),
+            Self::Underflow => write!(f, "result negative"),
+            Self::NoNumbers => write!(f, "no numbers"),
         }
     }
+}
 
-    ret.into_iter()
-        .map(|(data, kind)| Problem {
-            data,
-            kind: kind.unwrap(),
-        })
-        .collect()
+impl Error for EvalError {}
+
+/// A problem which could not be evaluated, `problem` is its index in the worksheet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProblemError {
+    pub problem: usize,
+    pub error: EvalError,
 }
 
-#[aoc_generator(day6, part2)]
-fn parse_part2(input: &str) -> Input {
-    let strides = input
-        .lines()
-        .last()
-        .unwrap()
-        .split_at(1)
-        .1
-        .split_inclusive(['+', '*'])
-        .map(|slice| slice.chars().count())
-        .with_position()
-        .map(|(pos, v)| if pos == Position::Last { v + 1 } else { v });
-    let mut ret: Vec<(Vec<u64>, _)> = strides
-        .clone()
-        .map(|length| (vec![0; length - 1], None))
-        .collect();
-
-    let mut lines = input
-        .lines()
-        .map(|line| line.chars().collect_vec())
-        .collect_vec();
-
-    for (column, v) in lines
-        .iter_mut()
-        .flat_map(|line| split_arbitrary_mut(line, strides.clone()).enumerate())
-    {
-        if v.contains(&'+') {
-            ret[column].1 = Some(Kind::Add);
-        } else if v.contains(&'*') {
-            ret[column].1 = Some(Kind::Mul);
-        } else {
-            for (i, digit) in v.iter().enumerate() {
-                if digit.is_whitespace() {
-                    continue;
-                }
+impl Display for ProblemError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "problem {}: {}", self.problem, self.error)
+    }
+}
 
-                let digit = format!("{digit}").parse().unwrap();
-                if let Some(old) = ret[column].0.get_mut(i) {
-                    *old *= 10;
-                    *old += digit;
-                } else {
-                    ret[column].0.push(digit);
-                }
-            }
+impl Error for ProblemError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub data: Vec<u64>,
+    pub kind: Kind,
+}
+
+/// The result of a problem, only using a big integer if it does not fit in a [`u64`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Small(u64),
+    Big(BigUint),
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Small(v) => write!(f, "{v}"),
+            Self::Big(v) => write!(f, "{v}"),
         }
     }
+}
 
-    ret.into_iter()
-        .map(|(data, kind)| Problem {
-            data,
-            kind: kind.unwrap(),
-        })
-        .collect()
+impl From<Value> for BigUint {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Small(v) => v.into(),
+            Value::Big(v) => v,
+        }
+    }
 }
 
-pub fn split_arbitrary_mut<T>(
-    slice: &mut [T],
-    sizes: impl IntoIterator<Item = usize>,
-) -> impl Iterator<Item = &mut [T]> {
-    SplitArbitraryMut {
-        slice,
-        sizes: sizes.into_iter(),
+impl Problem {
+    /// Results which might need more bits than this are an [`EvalError::Overflow`]
+    pub const MAX_BITS: u64 = 1 << 24;
+
+    /// Evaluates the problem with checked [`u64`] arithmetic, switching to a big integer if that overflows.
+    ///
+    /// # Errors
+    /// If any step of the calculation fails.
+    pub fn evaluate(&self) -> Result<Value, EvalError> {
+        match self.evaluate_small() {
+            Ok(v) => Ok(Value::Small(v)),
+            Err(EvalError::Overflow) => self.evaluate_big().map(Value::Big),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn evaluate_small(&self) -> Result<u64, EvalError> {
+        let Some((first, rest)) = self.data.split_first() else {
+            return self.evaluate_empty();
+        };
+
+        rest.iter().try_fold(*first, |acc, v| match self.kind {
+            Kind::Add => acc.checked_add(*v).ok_or(EvalError::Overflow),
+            Kind::Mul => acc.checked_mul(*v).ok_or(EvalError::Overflow),
+            Kind::Sub => acc.checked_sub(*v).ok_or(EvalError::Underflow),
+            Kind::Div => acc.checked_div(*v).ok_or(EvalError::DivisionByZero),
+            Kind::Min => Ok(min(acc, *v)),
+            Kind::Max => Ok(max(acc, *v)),
+            Kind::Pow => match u32::try_from(*v) {
+                Ok(exp) => acc.checked_pow(exp).ok_or(EvalError::Overflow),
+                // Only these do not grow with huge exponents
+                Err(_) if acc <= 1 => Ok(acc),
+                Err(_) => Err(EvalError::Overflow),
+            },
+        })
+    }
+
+    fn evaluate_big(&self) -> Result<BigUint, EvalError> {
+        let Some((first, rest)) = self.data.split_first() else {
+            return self.evaluate_empty().map(BigUint::from);
+        };
+
+        rest.iter()
+            .try_fold(BigUint::from(*first), |acc, v| match self.kind {
+                Kind::Add => Ok(acc + v),
+                Kind::Mul => Ok(acc * v),
+                Kind::Sub => {
+                    if acc < BigUint::from(*v) {
+                        Err(EvalError::Underflow)
+                    } else {
+                        Ok(acc - v)
+                    }
+                }
+                Kind::Div => {
+                    if *v == 0 {
+                        Err(EvalError::DivisionByZero)
+                    } else {
+                        Ok(acc / v)
+                    }
+                }
+                Kind::Min => Ok(min(acc, BigUint::from(*v))),
+                Kind::Max => Ok(max(acc, BigUint::from(*v))),
+                Kind::Pow => {
+                    if acc <= BigUint::from(1u8) {
+                        // Only these do not grow with huge exponents
+                        Ok(if *v == 0 { BigUint::from(1u8) } else { acc })
+                    } else if acc.bits().saturating_mul(*v) > Self::MAX_BITS {
+                        // The result has at most this many bits, so check before spending ages calculating it
+                        Err(EvalError::Overflow)
+                    } else {
+                        let exp = u32::try_from(*v).expect("Exponent is at most MAX_BITS");
+                        Ok(acc.pow(exp))
+                    }
+                }
+            })
+    }
+
+    const fn evaluate_empty(&self) -> Result<u64, EvalError> {
+        match self.kind {
+            Kind::Add => Ok(0),
+            Kind::Mul => Ok(1),
+            _ => Err(EvalError::NoNumbers),
+        }
     }
 }
 
-struct SplitArbitraryMut<'a, T, C: Iterator<Item = usize>> {
-    slice: &'a mut [T],
-    sizes: C,
+/// Evaluates every problem on its own.
+#[must_use]
+pub fn evaluate_all(problems: &[Problem]) -> Vec<Result<Value, EvalError>> {
+    problems.iter().map(Problem::evaluate).collect()
 }
 
-impl<'a, T, C: Iterator<Item = usize>> Iterator for SplitArbitraryMut<'a, T, C> {
-    type Item = &'a mut [T];
+/// Sums up the results of all problems.
+///
+/// # Errors
+/// Returns the first problem which could not be evaluated, or for which the sum overflowed.
+/// Use [`grand_total_big`] to not fail on overflow.
+pub fn grand_total(problems: &[Problem]) -> Result<u64, ProblemError> {
+    problems
+        .iter()
+        .enumerate()
+        .try_fold(0u64, |total, (problem, prob)| {
+            prob.evaluate()
+                .and_then(|v| match v {
+                    Value::Small(v) => total.checked_add(v).ok_or(EvalError::Overflow),
+                    Value::Big(_) => Err(EvalError::Overflow),
+                })
+                .map_err(|error| ProblemError { problem, error })
+        })
+}
+
+/// Sums up the results of all problems at arbitrary precision.
+///
+/// # Errors
+/// Returns the first problem which could not be evaluated.
+pub fn grand_total_big(problems: &[Problem]) -> Result<BigUint, ProblemError> {
+    problems
+        .iter()
+        .enumerate()
+        .try_fold(BigUint::ZERO, |total, (problem, prob)| {
+            prob.evaluate()
+                .map(|v| total + BigUint::from(v))
+                .map_err(|error| ProblemError { problem, error })
+        })
+}
+
+type Input = Vec<Problem>;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let next = self.sizes.next();
+/// Why a worksheet could not be read. Lines and columns are 1-based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    Empty,
+    /// The problem spanning `columns` has no operator in the last line
+    MissingOperator {
+        columns: RangeInclusive<usize>,
+    },
+    /// The problem spanning `columns` has more than one operator in the last line
+    MultipleOperators {
+        columns: RangeInclusive<usize>,
+    },
+    InvalidCharacter {
+        line: usize,
+        column: usize,
+        c: char,
+    },
+    /// The number starting at `line` and `column` does not fit in a [`u64`]
+    NumberTooLarge {
+        line: usize,
+        column: usize,
+    },
+    /// A line of a problem has a blank at `column` between its digits, so it is not a single number
+    GapInNumber {
+        line: usize,
+        column: usize,
+    },
+}
 
-        match next {
-            Some(size) => {
-                let slice = mem::take(&mut self.slice);
-                let split = slice.split_at_mut(size);
-                self.slice = split.1;
-                Some(split.0)
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "worksheet is empty"),
+            Self::MissingOperator { columns } => write!(
+                f,
+                "problem in columns {}-{} has no operator",
+                columns.start(),
+                columns.end()
+            ),
+            Self::MultipleOperators { columns } => write!(
+                f,
+                "problem in columns {}-{} has multiple operators",
+                columns.start(),
+                columns.end()
+            ),
+            Self::InvalidCharacter { line, column, c } => {
+                write!(f, "{line}:{column}: unexpected character {c:?}")
+            }
+            Self::NumberTooLarge { line, column } => {
+                write!(f, "{line}:{column}: number too large")
             }
-            None => None,
+            Self::GapInNumber { line, column } => {
+                write!(f, "{line}:{column}: gap inside a number")
+            }
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// How the numbers of a problem are written down
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Every line is a number, from the top to the bottom line
+    Rows,
+    /// Every column is a number read top to bottom, from the leftmost to the rightmost column
+    Columns,
+    /// Like [`Direction::Columns`], but from the rightmost to the leftmost column
+    ColumnsRightToLeft,
+}
+
+/// A grid of problems with their operators in the last line.
+/// Problems are separated by columns which are blank in every line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Worksheet {
+    lines: Vec<Vec<char>>,
+    width: usize,
+}
+
+impl Worksheet {
+    /// # Errors
+    /// If there are no lines.
+    pub fn new(input: &str) -> Result<Self, ParseError> {
+        let lines = input
+            .lines()
+            .map(|line| line.chars().collect_vec())
+            .collect_vec();
+        if lines.is_empty() {
+            return Err(ParseError::Empty);
         }
+        let width = lines.iter().map(Vec::len).max().unwrap_or_default();
+
+        Ok(Self { lines, width })
+    }
+
+    /// Reads all problems from left to right, with their numbers in `direction`.
+    ///
+    /// # Errors
+    /// If a problem does not have exactly one operator, or a number is not valid.
+    pub fn problems(&self, direction: Direction) -> Result<Vec<Problem>, ParseError> {
+        self.problem_columns()
+            .into_iter()
+            .map(|columns| {
+                let kind = self.operator(columns.clone())?;
+                let number_lines = 0..self.lines.len() - 1;
+
+                let numbers = match direction {
+                    Direction::Rows => number_lines
+                        .filter_map(|line| {
+                            // Unlike columns, where numbers can be aligned differently, a row has nothing to fill gaps
+                            if let Some(column) = self.gap(line, columns.clone()) {
+                                return Some(Err(ParseError::GapInNumber {
+                                    line: line + 1,
+                                    column: column + 1,
+                                }));
+                            }
+                            self.number(columns.clone().map(|column| (line, column)))
+                        })
+                        .collect_vec(),
+                    Direction::Columns => columns
+                        .filter_map(|column| {
+                            self.number(number_lines.clone().map(|line| (line, column)))
+                        })
+                        .collect_vec(),
+                    Direction::ColumnsRightToLeft => columns
+                        .rev()
+                        .filter_map(|column| {
+                            self.number(number_lines.clone().map(|line| (line, column)))
+                        })
+                        .collect_vec(),
+                };
+
+                Ok(Problem {
+                    data: numbers.into_iter().collect::<Result<_, _>>()?,
+                    kind,
+                })
+            })
+            .collect()
+    }
+
+    // Lines might have been stripped of trailing spaces, so treat everything past their end as blank
+    fn at(&self, line: usize, column: usize) -> char {
+        self.lines[line].get(column).copied().unwrap_or(' ')
+    }
+
+    fn problem_columns(&self) -> Vec<RangeInclusive<usize>> {
+        (0..self.width)
+            .map(|column| (0..self.lines.len()).all(|line| self.at(line, column) == ' '))
+            .enumerate()
+            .chunk_by(|(_, blank)| *blank)
+            .into_iter()
+            .filter(|(blank, _)| !blank)
+            .map(|(_, columns)| {
+                let columns = columns.map(|(column, _)| column).collect_vec();
+                columns[0]..=columns[columns.len() - 1]
+            })
+            .collect()
+    }
+
+    fn operator(&self, columns: RangeInclusive<usize>) -> Result<Kind, ParseError> {
+        let line = self.lines.len() - 1;
+        let human_columns = columns.start() + 1..=columns.end() + 1;
+
+        columns
+            .map(|column| (column, self.at(line, column)))
+            .filter(|(_, c)| *c != ' ')
+            .map(|(column, c)| {
+                Kind::from_symbol(c).ok_or(ParseError::InvalidCharacter {
+                    line: line + 1,
+                    column: column + 1,
+                    c,
+                })
+            })
+            .at_most_one()
+            .map_err(|_| ParseError::MultipleOperators {
+                columns: human_columns.clone(),
+            })?
+            .ok_or(ParseError::MissingOperator {
+                columns: human_columns,
+            })?
+    }
+
+    /// The first blank column in `line` which has something on both sides within `columns`
+    fn gap(&self, line: usize, columns: RangeInclusive<usize>) -> Option<usize> {
+        let filled = columns
+            .filter(|column| self.at(line, *column) != ' ')
+            .collect_vec();
+        filled
+            .iter()
+            .tuple_windows()
+            .find(|(a, b)| *a + 1 != **b)
+            .map(|(a, _)| a + 1)
+    }
+
+    /// The digits at `positions` in order, or [`None`] if they are all blank
+    fn number(
+        &self,
+        positions: impl Iterator<Item = (usize, usize)>,
+    ) -> Option<Result<u64, ParseError>> {
+        let mut digits = positions
+            .map(|(line, column)| (line, column, self.at(line, column)))
+            .filter(|(_, _, c)| *c != ' ')
+            .peekable();
+        let &(first_line, first_column, _) = digits.peek()?;
+
+        Some(digits.try_fold(0u64, |number, (line, column, c)| {
+            let digit = c.to_digit(10).ok_or(ParseError::InvalidCharacter {
+                line: line + 1,
+                column: column + 1,
+                c,
+            })?;
+            number
+                .checked_mul(10)
+                .and_then(|number| number.checked_add(u64::from(digit)))
+                .ok_or(ParseError::NumberTooLarge {
+                    line: first_line + 1,
+                    column: first_column + 1,
+                })
+        }))
     }
 }
 
+#[aoc_generator(day6, part1)]
+fn parse_part1(input: &str) -> Result<Input, ParseError> {
+    Worksheet::new(input)?.problems(Direction::Rows)
+}
+
+#[aoc_generator(day6, part2)]
+fn parse_part2(input: &str) -> Result<Input, ParseError> {
+    Worksheet::new(input)?.problems(Direction::Columns)
+}
+
 #[aoc(day6, part1)]
-fn part1(input: &Input) -> String {
-    let count = input
-        .iter()
-        .map(|prob| match prob.kind {
-            Kind::Add => prob.data.iter().sum::<u64>(),
-            Kind::Mul => prob.data.iter().product(),
-        })
-        .sum::<u64>();
-    format!("{count}")
+fn part1(input: &Input) -> Result<String, ProblemError> {
+    let count = grand_total_big(input)?;
+    Ok(format!("{count}"))
 }
 
 #[aoc(day6, part2)]
-fn part2(input: &Input) -> String {
-    let count = input
-        .iter()
-        .map(|prob| match prob.kind {
-            Kind::Add => prob.data.iter().sum::<u64>(),
-            Kind::Mul => prob.data.iter().product(),
-        })
-        .sum::<u64>();
-    format!("{count}")
+fn part2(input: &Input) -> Result<String, ProblemError> {
+    let count = grand_total_big(input)?;
+    Ok(format!("{count}"))
 }
 
 #[cfg(test)]
@@ -165,11 +504,265 @@ mod tests {
 
     #[test]
     fn part1_example() {
-        assert_eq!(part1(&parse_part1(EXAMPLE)), "4277556");
+        assert_eq!(part1(&parse_part1(EXAMPLE).unwrap()).unwrap(), "4277556");
     }
 
     #[test]
     fn part2_example() {
-        assert_eq!(part2(&parse_part2(EXAMPLE)), "3263827");
+        assert_eq!(part2(&parse_part2(EXAMPLE).unwrap()).unwrap(), "3263827");
+    }
+
+    #[test]
+    fn extended_operators() {
+        const WORKSHEET: &str = "100 2  7 9 3 0\n 20 3 10 4 4 5\n  3 2  0 6 2 0\n-   ^  < > / /\n";
+        let problems = parse_part1(WORKSHEET).unwrap();
+        assert_eq!(
+            problems.iter().map(|prob| prob.kind).collect_vec(),
+            [
+                Kind::Sub,
+                Kind::Pow,
+                Kind::Min,
+                Kind::Max,
+                Kind::Div,
+                Kind::Div
+            ]
+        );
+        assert_eq!(
+            evaluate_all(&problems),
+            [
+                Ok(Value::Small(77)),
+                Ok(Value::Small(64)),
+                Ok(Value::Small(0)),
+                Ok(Value::Small(9)),
+                Ok(Value::Small(0)),
+                Err(EvalError::DivisionByZero)
+            ]
+        );
+        assert_eq!(
+            grand_total(&problems),
+            Err(ProblemError {
+                problem: 5,
+                error: EvalError::DivisionByZero
+            })
+        );
+
+        let failing = [
+            (Kind::Sub, vec![1, 2], EvalError::Underflow),
+            (Kind::Pow, vec![2, u64::MAX], EvalError::Overflow),
+            (
+                Kind::Pow,
+                vec![u64::MAX, u64::from(u32::MAX)],
+                EvalError::Overflow,
+            ),
+            (
+                Kind::Pow,
+                vec![2, Problem::MAX_BITS + 1],
+                EvalError::Overflow,
+            ),
+            (Kind::Min, vec![], EvalError::NoNumbers),
+        ];
+        for (kind, data, error) in failing {
+            assert_eq!(Problem { data, kind }.evaluate(), Err(error));
+        }
+        assert_eq!(
+            Problem {
+                data: vec![1, u64::MAX],
+                kind: Kind::Pow
+            }
+            .evaluate(),
+            Ok(Value::Small(1))
+        );
+    }
+
+    #[test]
+    fn big_results() {
+        let mul = Problem {
+            data: vec![u64::MAX, 2, 3],
+            kind: Kind::Mul,
+        };
+        let pow = Problem {
+            data: vec![2, 64],
+            kind: Kind::Pow,
+        };
+        let add = Problem {
+            data: vec![u64::MAX, u64::MAX, 1],
+            kind: Kind::Add,
+        };
+        assert_eq!(
+            mul.evaluate(),
+            Ok(Value::Big(BigUint::from(u64::MAX) * 6u8))
+        );
+        assert_eq!(pow.evaluate().unwrap().to_string(), "18446744073709551616");
+        let huge = Problem {
+            data: vec![2, 1 << 20],
+            kind: Kind::Pow,
+        };
+        assert_eq!(
+            BigUint::from(huge.evaluate().unwrap()).bits(),
+            (1 << 20) + 1
+        );
+        assert_eq!(
+            add.evaluate(),
+            Ok(Value::Big(BigUint::from(u64::MAX) * 2u8 + 1u8))
+        );
+
+        let problems = [
+            pow,
+            Problem {
+                data: vec![5],
+                kind: Kind::Add,
+            },
+        ];
+        assert_eq!(
+            grand_total(&problems),
+            Err(ProblemError {
+                problem: 0,
+                error: EvalError::Overflow
+            })
+        );
+        assert_eq!(
+            grand_total_big(&problems).unwrap().to_string(),
+            "18446744073709551621"
+        );
+    }
+
+    #[test]
+    fn extended_operators_part2() {
+        let problems = parse_part2(&EXAMPLE.replace('*', "<")).unwrap();
+        assert_eq!(
+            problems.iter().map(|prob| prob.kind).collect_vec(),
+            [Kind::Min, Kind::Add, Kind::Min, Kind::Add]
+        );
+        assert_eq!(problems[0].evaluate(), Ok(Value::Small(1)));
+    }
+
+    #[test]
+    fn part2_ragged_layout() {
+        let stripped = EXAMPLE.lines().map(str::trim_end).join("\n");
+        assert_eq!(part2(&parse_part2(&stripped).unwrap()).unwrap(), "3263827");
+
+        // Operators do not have to be in the first column of a problem
+        let problems = parse_part2("12  3\n4  56\n +  *").unwrap();
+        assert_eq!(
+            problems,
+            [
+                Problem {
+                    data: vec![14, 2],
+                    kind: Kind::Add
+                },
+                Problem {
+                    data: vec![5, 36],
+                    kind: Kind::Mul
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn part2_errors() {
+        assert_eq!(parse_part2(""), Err(ParseError::Empty));
+        assert_eq!(
+            parse_part2("12 34\n56 78\n+"),
+            Err(ParseError::MissingOperator { columns: 4..=5 })
+        );
+        assert_eq!(
+            parse_part2("12 34\n56 78\n++ *"),
+            Err(ParseError::MultipleOperators { columns: 1..=2 })
+        );
+        assert_eq!(
+            parse_part2("12 34\n5x 78\n+  *"),
+            Err(ParseError::InvalidCharacter {
+                line: 2,
+                column: 2,
+                c: 'x'
+            })
+        );
+        assert_eq!(
+            parse_part2("12 34\n56 78\n+  %"),
+            Err(ParseError::InvalidCharacter {
+                line: 3,
+                column: 4,
+                c: '%'
+            })
+        );
+        assert_eq!(
+            parse_part2(&format!("{}+", "9\n".repeat(20))),
+            Err(ParseError::NumberTooLarge { line: 1, column: 1 })
+        );
+    }
+
+    #[test]
+    fn reading_directions() {
+        let worksheet = Worksheet::new("64 12\n 3 34\n 2 1\n-  /").unwrap();
+        let read = |direction| {
+            worksheet
+                .problems(direction)
+                .unwrap()
+                .into_iter()
+                .map(|prob| prob.data)
+                .collect_vec()
+        };
+        assert_eq!(read(Direction::Rows), [vec![64, 3, 2], vec![12, 34, 1]]);
+        assert_eq!(read(Direction::Columns), [vec![6, 432], vec![131, 24]]);
+        assert_eq!(
+            read(Direction::ColumnsRightToLeft),
+            [vec![432, 6], vec![24, 131]]
+        );
+
+        // Both parts are just different directions on the same worksheet
+        let worksheet = Worksheet::new(EXAMPLE).unwrap();
+        assert_eq!(
+            worksheet.problems(Direction::Rows).unwrap(),
+            parse_part1(EXAMPLE).unwrap()
+        );
+        assert_eq!(
+            worksheet.problems(Direction::Columns).unwrap(),
+            parse_part2(EXAMPLE).unwrap()
+        );
+        // Columns are folded from left to right, so the order matters for `-`
+        assert_eq!(
+            part2(&parse_part2("12 \n34 \n-  ").unwrap()),
+            Err(ProblemError {
+                problem: 0,
+                error: EvalError::Underflow
+            })
+        );
+        assert_eq!(
+            Worksheet::new("1 2\n+ -\n")
+                .unwrap()
+                .problems(Direction::Rows),
+            Ok(vec![
+                Problem {
+                    data: vec![1],
+                    kind: Kind::Add
+                },
+                Problem {
+                    data: vec![2],
+                    kind: Kind::Sub
+                }
+            ])
+        );
+        assert_eq!(
+            parse_part1("1 2\n345\n+  \n"),
+            Err(ParseError::GapInNumber { line: 1, column: 2 })
+        );
+        assert_eq!(
+            parse_part1("12  4\n3456 \n+   \n"),
+            Err(ParseError::GapInNumber { line: 1, column: 3 })
+        );
+        // Columns can have gaps, when numbers are aligned differently
+        assert_eq!(
+            Worksheet::new("1 2\n345\n+  \n")
+                .unwrap()
+                .problems(Direction::Columns),
+            Ok(vec![Problem {
+                data: vec![13, 4, 25],
+                kind: Kind::Add
+            }])
+        );
+        assert_eq!(
+            parse_part1(&format!("{}\n*", "9".repeat(20))),
+            Err(ParseError::NumberTooLarge { line: 1, column: 1 })
+        );
     }
 }
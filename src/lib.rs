@@ -1,15 +1,14 @@
-#![feature(get_disjoint_mut_helpers)]
-
-mod day_1;
+pub mod day_1;
 mod day_10;
 mod day_11;
 mod day_12;
-mod day_2;
-mod day_3;
-mod day_4;
-mod day_5;
-mod day_6;
+pub mod day_2;
+pub mod day_3;
+pub mod day_4;
+pub mod day_5;
+pub mod day_6;
 mod day_7;
 mod day_8;
 mod day_9;
+pub mod util;
 aoc_runner_derive::aoc_lib! {year = 2025 }
@@ -0,0 +1,362 @@
+use std::{error::Error, fmt::Display, mem};
+
+/// The sizes add up to more than the length of the slice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TooShort {
+    pub len: usize,
+    /// The sizes are only summed up until they no longer fit, so at least this many are required
+    pub required: usize,
+}
+
+impl Display for TooShort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "slice of length {} is too short, at least {} required",
+            self.len, self.required
+        )
+    }
+}
+
+impl Error for TooShort {}
+
+/// How much of a slice of length `len` is covered by `sizes`.
+fn covered_len(mut sizes: impl Iterator<Item = usize>, len: usize) -> Result<usize, TooShort> {
+    sizes.try_fold(0, |covered: usize, size| {
+        let required = covered.saturating_add(size);
+        if required > len {
+            Err(TooShort { len, required })
+        } else {
+            Ok(required)
+        }
+    })
+}
+
+const TOO_SHORT: &str = "Sizes add up to more than the slice length";
+
+/// Splits `slice` into consecutive parts of the given `sizes`.
+/// Elements after the last part can be retrieved with [`SplitArbitrary::into_remainder`].
+///
+/// The returned iterator panics once a part does not fit in the rest of the slice,
+/// use [`try_split_arbitrary`] to check all sizes up front.
+pub fn split_arbitrary<T, I: IntoIterator<Item = usize>>(
+    slice: &[T],
+    sizes: I,
+) -> SplitArbitrary<'_, T, I::IntoIter> {
+    SplitArbitrary {
+        slice,
+        sizes: sizes.into_iter(),
+        remainder: None,
+    }
+}
+
+/// Like [`split_arbitrary`], but checks that all sizes fit before splitting anything.
+///
+/// # Errors
+/// If the sizes add up to more than the length of `slice`.
+pub fn try_split_arbitrary<T, I>(
+    slice: &[T],
+    sizes: I,
+) -> Result<SplitArbitrary<'_, T, I::IntoIter>, TooShort>
+where
+    I: IntoIterator<Item = usize>,
+    I::IntoIter: Clone,
+{
+    let sizes = sizes.into_iter();
+    let (slice, remainder) = slice.split_at(covered_len(sizes.clone(), slice.len())?);
+
+    Ok(SplitArbitrary {
+        slice,
+        sizes,
+        remainder: Some(remainder),
+    })
+}
+
+/// Iterator returned by [`split_arbitrary`] and [`try_split_arbitrary`]
+#[derive(Debug, Clone)]
+pub struct SplitArbitrary<'a, T, I> {
+    slice: &'a [T],
+    sizes: I,
+    /// Everything after the last part, once it is known
+    remainder: Option<&'a [T]>,
+}
+
+impl<'a, T, I: Iterator<Item = usize>> SplitArbitrary<'a, T, I> {
+    /// All elements after the last part, skipping any parts which were not returned yet.
+    ///
+    /// # Panics
+    /// If the remaining sizes do not fit in the rest of the slice.
+    #[must_use]
+    pub fn into_remainder(self) -> &'a [T] {
+        if let Some(remainder) = self.remainder {
+            return remainder;
+        }
+        let covered = covered_len(self.sizes, self.slice.len()).expect(TOO_SHORT);
+        &self.slice[covered..]
+    }
+}
+
+impl<'a, T, I: Iterator<Item = usize>> Iterator for SplitArbitrary<'a, T, I> {
+    type Item = &'a [T];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let size = self.sizes.next()?;
+        assert!(size <= self.slice.len(), "{TOO_SHORT}");
+
+        let (part, rest) = self.slice.split_at(size);
+        self.slice = rest;
+        Some(part)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.sizes.size_hint()
+    }
+}
+
+impl<T, I: DoubleEndedIterator<Item = usize> + Clone> DoubleEndedIterator
+    for SplitArbitrary<'_, T, I>
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        // The last part ends where the remainder starts, which needs all sizes to be known
+        if self.remainder.is_none() {
+            let covered = covered_len(self.sizes.clone(), self.slice.len()).expect(TOO_SHORT);
+            let (slice, remainder) = self.slice.split_at(covered);
+            self.slice = slice;
+            self.remainder = Some(remainder);
+        }
+
+        let size = self.sizes.next_back()?;
+        let (rest, part) = self.slice.split_at(self.slice.len() - size);
+        self.slice = rest;
+        Some(part)
+    }
+}
+
+/// Mutable version of [`split_arbitrary`].
+pub fn split_arbitrary_mut<T, I: IntoIterator<Item = usize>>(
+    slice: &mut [T],
+    sizes: I,
+) -> SplitArbitraryMut<'_, T, I::IntoIter> {
+    SplitArbitraryMut {
+        slice,
+        sizes: sizes.into_iter(),
+        remainder: None,
+    }
+}
+
+/// Mutable version of [`try_split_arbitrary`].
+///
+/// # Errors
+/// If the sizes add up to more than the length of `slice`.
+pub fn try_split_arbitrary_mut<T, I>(
+    slice: &mut [T],
+    sizes: I,
+) -> Result<SplitArbitraryMut<'_, T, I::IntoIter>, TooShort>
+where
+    I: IntoIterator<Item = usize>,
+    I::IntoIter: Clone,
+{
+    let sizes = sizes.into_iter();
+    let covered = covered_len(sizes.clone(), slice.len())?;
+    let (slice, remainder) = slice.split_at_mut(covered);
+
+    Ok(SplitArbitraryMut {
+        slice,
+        sizes,
+        remainder: Some(remainder),
+    })
+}
+
+/// Iterator returned by [`split_arbitrary_mut`] and [`try_split_arbitrary_mut`]
+#[derive(Debug)]
+pub struct SplitArbitraryMut<'a, T, I> {
+    slice: &'a mut [T],
+    sizes: I,
+    /// Everything after the last part, once it is known
+    remainder: Option<&'a mut [T]>,
+}
+
+impl<'a, T, I: Iterator<Item = usize>> SplitArbitraryMut<'a, T, I> {
+    /// All elements after the last part, skipping any parts which were not returned yet.
+    ///
+    /// # Panics
+    /// If the remaining sizes do not fit in the rest of the slice.
+    #[must_use]
+    pub fn into_remainder(self) -> &'a mut [T] {
+        if let Some(remainder) = self.remainder {
+            return remainder;
+        }
+        let covered = covered_len(self.sizes, self.slice.len()).expect(TOO_SHORT);
+        &mut self.slice[covered..]
+    }
+}
+
+impl<'a, T, I: Iterator<Item = usize>> Iterator for SplitArbitraryMut<'a, T, I> {
+    type Item = &'a mut [T];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let size = self.sizes.next()?;
+        assert!(size <= self.slice.len(), "{TOO_SHORT}");
+
+        let (part, rest) = mem::take(&mut self.slice).split_at_mut(size);
+        self.slice = rest;
+        Some(part)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.sizes.size_hint()
+    }
+}
+
+impl<T, I: DoubleEndedIterator<Item = usize> + Clone> DoubleEndedIterator
+    for SplitArbitraryMut<'_, T, I>
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remainder.is_none() {
+            let covered = covered_len(self.sizes.clone(), self.slice.len()).expect(TOO_SHORT);
+            let (slice, remainder) = mem::take(&mut self.slice).split_at_mut(covered);
+            self.slice = slice;
+            self.remainder = Some(remainder);
+        }
+
+        let size = self.sizes.next_back()?;
+        let slice = mem::take(&mut self.slice);
+        let (rest, part) = slice.split_at_mut(slice.len() - size);
+        self.slice = rest;
+        Some(part)
+    }
+}
+
+/// Deterministic pseudo random numbers, so tests can generate inputs without a dependency
+#[cfg(test)]
+pub(crate) struct TestRng(u32);
+
+#[cfg(test)]
+impl TestRng {
+    pub(crate) const fn new(seed: u32) -> Self {
+        Self(seed)
+    }
+
+    /// A number in `0..max`
+    pub(crate) fn below(&mut self, max: usize) -> usize {
+        self.0 = self.0.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        (self.0 >> 16) as usize % max
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use super::*;
+
+    const DATA: [u8; 10] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9];
+
+    #[test]
+    fn split() {
+        let parts = split_arbitrary(&DATA, [3, 0, 2]).collect_vec();
+        assert_eq!(parts, [&[0, 1, 2][..], &[], &[3, 4]]);
+        assert_eq!(
+            split_arbitrary(&DATA, [3, 0, 2]).into_remainder(),
+            [5, 6, 7, 8, 9]
+        );
+        assert_eq!(split_arbitrary(&DATA, [10]).into_remainder(), []);
+        assert_eq!(split_arbitrary(&DATA, []).into_remainder(), DATA);
+
+        let empty: [u8; 0] = [];
+        assert_eq!(split_arbitrary(&empty, [0, 0]).collect_vec(), [&[]; 2]);
+        assert_eq!(split_arbitrary(&empty, []).count(), 0);
+
+        // Parts which were not returned yet are not part of the remainder
+        let mut iter = split_arbitrary(&DATA, [1, 2, 3]);
+        assert_eq!(iter.next(), Some(&[0][..]));
+        assert_eq!(iter.into_remainder(), [6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn split_mut() {
+        let mut data = DATA;
+        for (i, part) in split_arbitrary_mut(&mut data, [2, 3]).enumerate() {
+            part.fill(u8::try_from(i).unwrap() + 100);
+        }
+        assert_eq!(data, [100, 100, 101, 101, 101, 5, 6, 7, 8, 9]);
+
+        let remainder = split_arbitrary_mut(&mut data, [4]).into_remainder();
+        remainder.fill(0);
+        assert_eq!(data, [100, 100, 101, 101, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    #[should_panic = "Sizes add up to more than the slice length"]
+    fn too_short() {
+        split_arbitrary(&DATA, [5, 6]).for_each(drop);
+    }
+
+    #[test]
+    #[should_panic = "Sizes add up to more than the slice length"]
+    fn too_short_remainder() {
+        let mut data = DATA;
+        let _ = split_arbitrary_mut(&mut data, [5, 6]).into_remainder();
+    }
+
+    #[test]
+    fn try_split() {
+        let mut data = DATA;
+        assert_eq!(
+            try_split_arbitrary(&DATA, [5, 6]).err(),
+            Some(TooShort {
+                len: 10,
+                required: 11
+            })
+        );
+        assert_eq!(
+            try_split_arbitrary_mut(&mut data, [usize::MAX, 1]).err(),
+            Some(TooShort {
+                len: 10,
+                required: usize::MAX
+            })
+        );
+        assert_eq!(
+            try_split_arbitrary(&[0u8; 0], [1]).err(),
+            Some(TooShort {
+                len: 0,
+                required: 1
+            })
+        );
+
+        let parts = try_split_arbitrary(&DATA, [4, 6]).unwrap().collect_vec();
+        assert_eq!(parts, [&DATA[..4], &DATA[4..]]);
+        let mut iter = try_split_arbitrary_mut(&mut data, [1, 2]).unwrap();
+        iter.next().unwrap()[0] = 42;
+        assert_eq!(iter.into_remainder(), [3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(data[0], 42);
+    }
+
+    #[test]
+    fn double_ended() {
+        let parts = split_arbitrary(&DATA, [1, 2, 3]).rev().collect_vec();
+        assert_eq!(parts, [&[3, 4, 5][..], &[1, 2], &[0]]);
+
+        let mut iter = split_arbitrary(&DATA, [1, 2, 3, 0]);
+        assert_eq!(iter.next_back(), Some(&[][..]));
+        assert_eq!(iter.next(), Some(&[0][..]));
+        assert_eq!(iter.next_back(), Some(&[3, 4, 5][..]));
+        assert_eq!(iter.next(), Some(&[1, 2][..]));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+        assert_eq!(iter.into_remainder(), [6, 7, 8, 9]);
+
+        let mut data = DATA;
+        let mut iter = try_split_arbitrary_mut(&mut data, [3, 3]).unwrap();
+        iter.next_back().unwrap().fill(0);
+        iter.next().unwrap().fill(1);
+        assert_eq!(iter.into_remainder(), [6, 7, 8, 9]);
+        assert_eq!(data, [1, 1, 1, 0, 0, 0, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    #[should_panic = "Sizes add up to more than the slice length"]
+    fn double_ended_too_short() {
+        let _ = split_arbitrary(&DATA, [5, 6]).next_back();
+    }
+}